@@ -0,0 +1,62 @@
+use crate::{
+    INVESTOR_ACCOUNTING_COMPUTE_UNITS, INVESTOR_PAYOUT_COMPUTE_UNITS, MIN_COMPUTE_UNITS_PER_POSITION,
+    PAGE_SETTLEMENT_COMPUTE_UNITS,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+
+/// Compute Budget program ID
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("ComputeBudget111111111111111111111111111111");
+
+/// Highest compute unit limit a transaction can request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// ComputeBudgetInstruction::SetComputeUnitLimit variant index
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+
+/// @notice Build the investor remaining accounts of a distribute_fees page
+/// @dev Produces [stream_1, ata_1, stream_2, ata_2, ...]: streams are read-only, investor ATAs writable, neither signs
+/// @dev Pairs must already be in strictly ascending stream order across the day, the crank rejects anything else
+/// @param pairs (Streamflow stream, investor quote ATA) pairs for the page
+/// @return Vec<AccountMeta> The remaining accounts for the page's investor pairs
+pub fn build_distribute_remaining_accounts(pairs: &[(Pubkey, Pubkey)]) -> Vec<AccountMeta> {
+    pairs
+        .iter()
+        .flat_map(|(stream, investor_ata)| {
+            [AccountMeta::new_readonly(*stream, false), AccountMeta::new(*investor_ata, false)]
+        })
+        .collect()
+}
+
+/// @notice Estimate the compute units a distribute_fees page needs for its investor pairs
+/// @dev One claim CPI plus page settlement, then the accounting and payout passes for every pair, capped at
+/// @dev MAX_COMPUTE_UNIT_LIMIT
+/// @param pair_count Number of (stream, investor ATA) pairs on the page
+/// @return u32 The compute unit limit to request
+pub fn distribute_fees_compute_units(pair_count: usize) -> u32 {
+    let per_pair_units = INVESTOR_ACCOUNTING_COMPUTE_UNITS + INVESTOR_PAYOUT_COMPUTE_UNITS;
+    let units = (pair_count as u64)
+        .saturating_mul(per_pair_units)
+        .saturating_add(MIN_COMPUTE_UNITS_PER_POSITION)
+        .saturating_add(PAGE_SETTLEMENT_COMPUTE_UNITS);
+    units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// @notice Build a ComputeBudgetInstruction::set_compute_unit_limit instruction
+/// @param units The compute unit limit to request
+/// @return Instruction The compute budget instruction
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![SET_COMPUTE_UNIT_LIMIT];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction { program_id: COMPUTE_BUDGET_PROGRAM_ID, accounts: vec![], data }
+}
+
+/// @notice Prepend a compute unit limit sized for the page to a distribute_fees instruction
+/// @dev The program can't raise its own budget, a page sent with the default limit stops early or reverts
+/// @param distribute_fees_ix The distribute_fees instruction of the page
+/// @param pair_count Number of (stream, investor ATA) pairs in the page's remaining accounts
+/// @return Vec<Instruction> [set_compute_unit_limit, distribute_fees], in transaction order
+pub fn distribute_fees_with_budget(distribute_fees_ix: Instruction, pair_count: usize) -> Vec<Instruction> {
+    vec![set_compute_unit_limit(distribute_fees_compute_units(pair_count)), distribute_fees_ix]
+}
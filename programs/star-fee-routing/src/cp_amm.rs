@@ -0,0 +1,237 @@
+use crate::FeeRoutingError;
+use anchor_lang::prelude::*;
+
+/// Anchor account discriminator of the DAMM V2 `Pool` account
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+/// Anchor account discriminator of the DAMM V2 `Position` account
+pub const POSITION_DISCRIMINATOR: [u8; 8] = [170, 188, 143, 228, 122, 64, 247, 208];
+
+/// DAMM V2 `CollectFeeMode::BothToken`, swap fees are taken in the output token so token A fees can accrue
+pub const COLLECT_FEE_MODE_BOTH_TOKEN: u8 = 0;
+
+/// DAMM V2 `CollectFeeMode::OnlyB`, swap fees are always taken in token B
+pub const COLLECT_FEE_MODE_ONLY_B: u8 = 1;
+
+/// Fixed-point shift of the DAMM V2 fee-per-liquidity accumulators
+const LIQUIDITY_SCALE: usize = 128;
+
+// Byte offsets into the DAMM V2 `Pool` account data, including the 8-byte discriminator.
+// The account starts with `pool_fees: PoolFeesStruct` (160 bytes), followed by the mints and vaults.
+const TOKEN_A_MINT_OFFSET: usize = 168;
+const TOKEN_B_MINT_OFFSET: usize = 200;
+const TOKEN_A_VAULT_OFFSET: usize = 232;
+const TOKEN_B_VAULT_OFFSET: usize = 264;
+const COLLECT_FEE_MODE_OFFSET: usize = 484;
+const FEE_A_PER_LIQUIDITY_OFFSET: usize = 488;
+const FEE_B_PER_LIQUIDITY_OFFSET: usize = 520;
+
+/// Minimum pool account length required to read every parsed field
+pub const POOL_MIN_LEN: usize = FEE_B_PER_LIQUIDITY_OFFSET + 32;
+
+// Byte offsets into the DAMM V2 `Position` account data, including the 8-byte discriminator.
+const POSITION_POOL_OFFSET: usize = 8;
+const POSITION_NFT_MINT_OFFSET: usize = 40;
+const FEE_A_CHECKPOINT_OFFSET: usize = 72;
+const FEE_B_CHECKPOINT_OFFSET: usize = 104;
+const FEE_A_PENDING_OFFSET: usize = 136;
+const FEE_B_PENDING_OFFSET: usize = 144;
+const UNLOCKED_LIQUIDITY_OFFSET: usize = 152;
+const VESTED_LIQUIDITY_OFFSET: usize = 168;
+const PERMANENT_LOCKED_LIQUIDITY_OFFSET: usize = 184;
+
+/// Minimum position account length required to read every parsed field
+pub const POSITION_MIN_LEN: usize = PERMANENT_LOCKED_LIQUIDITY_OFFSET + 16;
+
+/// Subset of the DAMM V2 (CP-AMM) `Pool` account used by the fee router
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DammV2Pool {
+    /// Token A mint (base token)
+    pub token_a_mint: Pubkey,
+    /// Token B mint (quote token)
+    pub token_b_mint: Pubkey,
+    /// Token A vault of the pool
+    pub token_a_vault: Pubkey,
+    /// Token B vault of the pool
+    pub token_b_vault: Pubkey,
+    /// Fee collection mode configured on the pool
+    pub collect_fee_mode: u8,
+    /// Accumulated token A fees per unit of liquidity (U256, little-endian)
+    pub fee_a_per_liquidity: [u8; 32],
+    /// Accumulated token B fees per unit of liquidity (U256, little-endian)
+    pub fee_b_per_liquidity: [u8; 32],
+}
+
+impl DammV2Pool {
+    /// Deserialize the router-relevant fields from raw DAMM V2 pool account data
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < POOL_MIN_LEN || data.get(..8) != Some(&POOL_DISCRIMINATOR[..]) {
+            msg!("Account data is not a DAMM V2 pool ({} bytes)", data.len());
+            return Err(FeeRoutingError::InvalidQuoteMint.into());
+        }
+
+        Ok(Self {
+            token_a_mint: read_pubkey(data, TOKEN_A_MINT_OFFSET)?,
+            token_b_mint: read_pubkey(data, TOKEN_B_MINT_OFFSET)?,
+            token_a_vault: read_pubkey(data, TOKEN_A_VAULT_OFFSET)?,
+            token_b_vault: read_pubkey(data, TOKEN_B_VAULT_OFFSET)?,
+            collect_fee_mode: read_u8(data, COLLECT_FEE_MODE_OFFSET)?,
+            fee_a_per_liquidity: read_u256(data, FEE_A_PER_LIQUIDITY_OFFSET)?,
+            fee_b_per_liquidity: read_u256(data, FEE_B_PER_LIQUIDITY_OFFSET)?,
+        })
+    }
+
+    /// Whether the pool only ever charges swap fees in token B, unknown modes are treated as unsafe
+    pub fn collects_fees_only_in_token_b(&self) -> bool {
+        self.collect_fee_mode == COLLECT_FEE_MODE_ONLY_B
+    }
+}
+
+/// Subset of the DAMM V2 (CP-AMM) `Position` account used by the fee router
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DammV2Position {
+    /// Pool the position belongs to
+    pub pool: Pubkey,
+    /// Mint of the NFT that represents ownership of the position
+    pub nft_mint: Pubkey,
+    /// Pool token A fee-per-liquidity at the last position update
+    pub fee_a_per_token_checkpoint: [u8; 32],
+    /// Pool token B fee-per-liquidity at the last position update
+    pub fee_b_per_token_checkpoint: [u8; 32],
+    /// Token A fees settled into the position but not yet claimed
+    pub fee_a_pending: u64,
+    /// Token B fees settled into the position but not yet claimed
+    pub fee_b_pending: u64,
+    /// Unlocked + vested + permanently locked liquidity
+    pub total_liquidity: u128,
+}
+
+impl DammV2Position {
+    /// Deserialize the router-relevant fields from raw DAMM V2 position account data
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < POSITION_MIN_LEN || data.get(..8) != Some(&POSITION_DISCRIMINATOR[..]) {
+            msg!("Account data is not a DAMM V2 position ({} bytes)", data.len());
+            return Err(FeeRoutingError::InvalidPositionOwner.into());
+        }
+
+        let mut total_liquidity = 0u128;
+        for offset in [UNLOCKED_LIQUIDITY_OFFSET, VESTED_LIQUIDITY_OFFSET, PERMANENT_LOCKED_LIQUIDITY_OFFSET] {
+            total_liquidity =
+                total_liquidity.checked_add(read_u128(data, offset)?).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+        }
+
+        Ok(Self {
+            pool: read_pubkey(data, POSITION_POOL_OFFSET)?,
+            nft_mint: read_pubkey(data, POSITION_NFT_MINT_OFFSET)?,
+            fee_a_per_token_checkpoint: read_u256(data, FEE_A_CHECKPOINT_OFFSET)?,
+            fee_b_per_token_checkpoint: read_u256(data, FEE_B_CHECKPOINT_OFFSET)?,
+            fee_a_pending: read_u64(data, FEE_A_PENDING_OFFSET)?,
+            fee_b_pending: read_u64(data, FEE_B_PENDING_OFFSET)?,
+            total_liquidity,
+        })
+    }
+
+    /// Token A fees a claim would currently pay out
+    pub fn claimable_fee_a(&self, pool: &DammV2Pool) -> Result<u64> {
+        claimable_fee(
+            self.total_liquidity,
+            &pool.fee_a_per_liquidity,
+            &self.fee_a_per_token_checkpoint,
+            self.fee_a_pending,
+        )
+    }
+
+    /// Token B fees a claim would currently pay out
+    pub fn claimable_fee_b(&self, pool: &DammV2Pool) -> Result<u64> {
+        claimable_fee(
+            self.total_liquidity,
+            &pool.fee_b_per_liquidity,
+            &self.fee_b_per_token_checkpoint,
+            self.fee_b_pending,
+        )
+    }
+}
+
+/// Settled pending fees plus the fees accrued since the checkpoint:
+/// pending + (liquidity * (fee_per_liquidity - checkpoint)) >> LIQUIDITY_SCALE, as DAMM V2 computes on claim
+pub fn claimable_fee(
+    liquidity: u128,
+    fee_per_liquidity: &[u8; 32],
+    checkpoint: &[u8; 32],
+    pending: u64,
+) -> Result<u64> {
+    let to_limbs = |bytes: &[u8; 32]| -> [u64; 4] {
+        std::array::from_fn(|i| u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap()))
+    };
+    let current = to_limbs(fee_per_liquidity);
+    let checkpoint = to_limbs(checkpoint);
+
+    // U256 subtraction, the pool accumulator can never fall behind a position checkpoint
+    let mut delta = [0u64; 4];
+    let mut borrow = false;
+    for ((limb, &current), &checkpoint) in delta.iter_mut().zip(current.iter()).zip(checkpoint.iter()) {
+        let (diff, borrow_a) = current.overflowing_sub(checkpoint);
+        let (diff, borrow_b) = diff.overflowing_sub(borrow as u64);
+        *limb = diff;
+        borrow = borrow_a || borrow_b;
+    }
+    if borrow {
+        return Err(FeeRoutingError::ArithmeticOverflow.into());
+    }
+
+    // 128-bit liquidity times 256-bit delta as a 384-bit product
+    let liquidity = [liquidity as u64, (liquidity >> 64) as u64];
+    let mut product = [0u64; 6];
+    for (i, &l) in liquidity.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, &d) in delta.iter().enumerate() {
+            let acc = (l as u128) * (d as u128) + product[i + j] as u128 + carry;
+            product[i + j] = acc as u64;
+            carry = acc >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+
+    // Shift right by the accumulator scale, the accrued fee must fit in a u64
+    let shift_limbs = LIQUIDITY_SCALE / 64;
+    if product[shift_limbs + 1..].iter().any(|&limb| limb != 0) {
+        return Err(FeeRoutingError::ArithmeticOverflow.into());
+    }
+
+    Ok(pending.checked_add(product[shift_limbs]).ok_or(FeeRoutingError::ArithmeticOverflow)?)
+}
+
+/// Read a bounds-checked byte from account data
+pub fn read_u8(data: &[u8], offset: usize) -> Result<u8> {
+    Ok(*data.get(offset).ok_or(FeeRoutingError::InvalidQuoteMint)?)
+}
+
+/// Read a bounds-checked little-endian u32 from account data
+pub fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data.get(offset..offset.saturating_add(4)).ok_or(FeeRoutingError::InvalidQuoteMint)?;
+    Ok(u32::from_le_bytes(bytes.try_into().map_err(|_| FeeRoutingError::InvalidQuoteMint)?))
+}
+
+/// Read a bounds-checked little-endian u64 from account data
+pub fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes = data.get(offset..offset.saturating_add(8)).ok_or(FeeRoutingError::InvalidQuoteMint)?;
+    Ok(u64::from_le_bytes(bytes.try_into().map_err(|_| FeeRoutingError::InvalidQuoteMint)?))
+}
+
+/// Read a bounds-checked little-endian u128 from account data
+pub fn read_u128(data: &[u8], offset: usize) -> Result<u128> {
+    let bytes = data.get(offset..offset.saturating_add(16)).ok_or(FeeRoutingError::InvalidQuoteMint)?;
+    Ok(u128::from_le_bytes(bytes.try_into().map_err(|_| FeeRoutingError::InvalidQuoteMint)?))
+}
+
+/// Read a bounds-checked raw 256-bit value from account data
+fn read_u256(data: &[u8], offset: usize) -> Result<[u8; 32]> {
+    let bytes = data.get(offset..offset.saturating_add(32)).ok_or(FeeRoutingError::InvalidQuoteMint)?;
+    Ok(bytes.try_into().map_err(|_| FeeRoutingError::InvalidQuoteMint)?)
+}
+
+/// Read a bounds-checked pubkey from account data
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    let bytes = data.get(offset..offset.saturating_add(32)).ok_or(FeeRoutingError::InvalidQuoteMint)?;
+    Ok(Pubkey::try_from(bytes).map_err(|_| FeeRoutingError::InvalidQuoteMint)?)
+}
@@ -5,7 +5,7 @@ pub enum FeeRoutingError {
     #[msg("Pool configuration would result in base token fees, which is not allowed")]
     BaseFeeDetected,
 
-    #[msg("Distribution can only be called once per 24 hour period")]
+    #[msg("Distribution can only be called once per distribution interval")]
     TooEarlyForDistribution,
 
     #[msg("Invalid quote mint - pool token order validation failed")]
@@ -49,4 +49,127 @@ pub enum FeeRoutingError {
 
     #[msg("Pool validation failed - cannot guarantee quote-only fees")]
     QuoteOnlyValidationFailed,
+
+    #[msg("Signer is not the global state authority")]
+    Unauthorized,
+
+    #[msg("Creator ATA mint does not match the quote mint")]
+    CreatorAtaMintMismatch,
+
+    #[msg("Investor ATA does not belong to the paired Streamflow recipient")]
+    StreamAtaMismatch,
+
+    #[msg("Stream already processed today - streams must be passed in strictly ascending order")]
+    DuplicateStreamInDistribution,
+
+    #[msg("Investor fee share must be between 0 and 10000 basis points")]
+    InvalidFeeShareBps,
+
+    #[msg("Program is paused - fee claiming and distribution are halted")]
+    ProgramPaused,
+
+    #[msg("Distribution interval must be at least one hour")]
+    InvalidDistributionInterval,
+
+    #[msg("Y0 total is zero - pass y0_total or streams with deposits")]
+    MissingY0Total,
+
+    #[msg("CP-AMM program does not match the DAMM V2 program ID")]
+    InvalidCpAmmProgram,
+
+    #[msg("Creator split must have 1-5 distinct recipients with non-zero bps summing to 10000")]
+    InvalidCreatorSplit,
+
+    #[msg("Investor pair is not in the allowlist or the proof is invalid")]
+    InvestorNotAllowlisted,
+
+    #[msg("Distribution progress belongs to a different vault")]
+    VaultSeedMismatch,
+
+    #[msg("Batch accounts do not match the vault seeds")]
+    BatchAccountsMismatch,
+
+    #[msg("Additional position accounts are missing or exceed the maximum")]
+    InvalidAdditionalPositions,
+
+    #[msg("Distribution progress account has an unknown layout")]
+    InvalidProgressLayout,
+
+    #[msg("A distribution day is in progress, resume it from the stored page cursor")]
+    DistributionInProgress,
+
+    #[msg("No distribution day is in progress")]
+    NoDistributionInProgress,
+
+    #[msg("Too many investor pairs in one distribution page")]
+    TooManyInvestorsInPage,
+
+    #[msg("Daily cap is below the minimum payout, check it is in quote base units")]
+    ImplausibleDailyCap,
+
+    #[msg("No investor pairs provided and the policy does not allow an empty investor set")]
+    NoInvestorsProvided,
+
+    #[msg("The honorary position still has unclaimed fees, crank them out first")]
+    UnclaimedFeesOutstanding,
+
+    #[msg("Investor pairs do not match the investors registered for this page")]
+    InvestorNotRegistered,
+
+    #[msg("Registered investor page ran out of compute, register fewer investors per page")]
+    RegistryPageTooLarge,
+
+    #[msg("Quote mint has too many decimals to represent one whole token in a u64")]
+    UnsupportedQuoteDecimals,
+
+    #[msg("Locked amounts don't fit the day's total_locked snapshot")]
+    InvalidLockedSnapshot,
+
+    #[msg("Creator-only vaults crank a single page 0 without investor accounts")]
+    InvalidCreatorOnlyPage,
+
+    #[msg("Vaults still hold honorary positions, transfer them out before closing the deployment")]
+    VaultsStillOpen,
+
+    #[msg("Account was written with a newer schema version than this program supports")]
+    UnsupportedStateVersion,
+
+    #[msg("Quote treasury balance shrank during the fee claim")]
+    ClaimReconciliationFailed,
+
+    #[msg("Memo program is missing or not the SPL Memo program")]
+    InvalidMemoProgram,
+
+    #[msg("Base fees must be claimed into a base treasury distinct from the quote treasury")]
+    InvalidClaimDestination,
+
+    #[msg("Raw lock can't lock more than was deposited")]
+    InvalidRawLock,
+
+    #[msg("Account is not a distribution receipt of this program")]
+    InvalidDistributionReceipt,
+
+    #[msg("Distribution receipt is within the days kept")]
+    ReceiptTooRecent,
+
+    #[msg("Bonfida vesting is not registered for the vault, or its destination changed since")]
+    UnregisteredBonfidaVesting,
+
+    #[msg("Token account is not the Bonfida vesting's destination")]
+    InvalidBonfidaDestination,
+
+    #[msg("Bonfida registry is full")]
+    BonfidaRegistryFull,
+
+    #[msg("Bonfida vesting holds more than the deposit registered for it")]
+    InvalidBonfidaDeposit,
+
+    #[msg("Per-investor cap ledger is required to cap a day of several pages")]
+    MissingInvestorCapLedger,
+
+    #[msg("Per-investor cap ledger is full")]
+    InvestorCapLedgerFull,
+
+    #[msg("Global state account has an unknown layout")]
+    InvalidGlobalStateLayout,
 }
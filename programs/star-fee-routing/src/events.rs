@@ -15,6 +15,21 @@ pub struct HonoraryPositionInitialized {
     pub upper_tick: i32,
     /// Quote mint
     pub quote_mint: Pubkey,
+    /// NFT mint of the position, needed to claim its fees
+    pub position_nft_mint: Pubkey,
+    /// Token account holding the position NFT
+    pub position_nft_account: Pubkey,
+}
+
+/// Event emitted at the end of a batch position initialization
+#[event]
+pub struct HonoraryPositionsBatchInitialized {
+    /// Number of vaults in the batch
+    pub requested: u32,
+    /// Number of positions created before the batch finished or ran low on compute
+    pub initialized: u32,
+    /// Timestamp of the batch
+    pub timestamp: i64,
 }
 
 /// Event emitted when quote fees are claimed
@@ -22,10 +37,29 @@ pub struct HonoraryPositionInitialized {
 pub struct QuoteFeesClaimed {
     /// Amount of quote fees claimed
     pub amount_claimed: u64,
+    /// Amount of base fees claimed, always 0 since a base claim aborts the crank
+    pub base_amount_claimed: u64,
     /// Quote mint
     pub quote_mint: Pubkey,
     /// Timestamp when claimed
     pub timestamp: i64,
+    /// Decimals of the quote mint, amounts are in its base units
+    pub quote_decimals: u8,
+}
+
+/// Event emitted by get_pending_fees with the fees a claim would currently collect
+#[event]
+pub struct PendingFees {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// The honorary position
+    pub position: Pubkey,
+    /// Claimable quote (token B) fees
+    pub pending_quote: u64,
+    /// Claimable base (token A) fees, must be 0 for a quote-only position
+    pub pending_base: u64,
+    /// Timestamp of the read
+    pub timestamp: i64,
 }
 
 /// Event emitted for each investor payout page
@@ -39,6 +73,104 @@ pub struct InvestorPayoutPage {
     pub total_distributed: u64,
     /// Timestamp of distribution
     pub timestamp: i64,
+    /// Whether this page also claimed the position fees, false on continuation pages
+    pub claimed_this_page: bool,
+    /// Locked amount of the page's participating streams, the pro-rata denominator when no weights are set
+    pub total_locked: u64,
+    /// min(investor_fee_share_bps, f_locked) the page's investor pool was sized with, before the creator floor
+    pub eligible_share_bps: u16,
+}
+
+/// Event emitted when the daily cap clips a page's investor pool, the excess stays with the creator
+#[event]
+pub struct DailyCapReached {
+    /// Investor pool before the cap
+    pub uncapped_amount: u64,
+    /// Daily cap on investor payouts
+    pub daily_cap: u64,
+    /// Investor pool after clipping to what is left of the cap
+    pub clipped_amount: u64,
+    /// Page the cap bound on
+    pub page_index: u32,
+    /// Timestamp of distribution
+    pub timestamp: i64,
+}
+
+/// Event emitted for every individual investor payout
+#[event]
+pub struct InvestorPayout {
+    /// Investor's quote token ATA that received the payout
+    pub investor_ata: Pubkey,
+    /// Streamflow stream the payout was computed from
+    pub stream: Pubkey,
+    /// Tokens still locked in the stream, summed over every stream of the page paying this ATA
+    pub locked_amount: u64,
+    /// Number of the page's streams paying this ATA, `stream` is the first of them
+    pub stream_count: u32,
+    /// Quote amount paid to the investor
+    pub payout_amount: u64,
+    /// Page index the payout belongs to
+    pub page_index: u32,
+    /// Timestamp of distribution
+    pub timestamp: i64,
+}
+
+/// Event emitted when the per-investor daily cap clips a payout, the excess is carried over
+#[event]
+pub struct InvestorCapReached {
+    /// Investor's quote token ATA
+    pub investor_ata: Pubkey,
+    /// First stream of the page paying this ATA
+    pub stream: Pubkey,
+    /// Investor's share before the cap, summed over the ATA's streams
+    pub uncapped_amount: u64,
+    /// Amount paid after the cap
+    pub clipped_amount: u64,
+    /// Page index the payout belongs to
+    pub page_index: u32,
+    /// Timestamp of distribution
+    pub timestamp: i64,
+}
+
+/// Why an investor payout could not be made
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayoutSkipReason {
+    /// The investor ATA is closed or not an initialized SPL token account
+    AccountClosed,
+    /// The investor ATA is frozen
+    AccountFrozen,
+    /// The investor ATA holds a different mint than the quote mint
+    MintMismatch,
+}
+
+/// Event emitted when an investor payout is skipped and carried over
+#[event]
+pub struct PayoutSkipped {
+    /// Investor's quote token ATA that could not receive the payout
+    pub investor_ata: Pubkey,
+    /// Streamflow stream the payout was computed from
+    pub stream: Pubkey,
+    /// Quote amount carried over instead of paid
+    pub amount: u64,
+    /// Why the payout was skipped
+    pub reason: PayoutSkipReason,
+    /// Page index the payout belongs to
+    pub page_index: u32,
+    /// Timestamp of distribution
+    pub timestamp: i64,
+}
+
+/// Event emitted when a stream that fails to deserialize is skipped by the crank
+#[event]
+pub struct StreamSkipped {
+    /// Streamflow stream that could not be read
+    pub stream: Pubkey,
+    /// Investor's quote token ATA paired with the stream
+    pub investor_ata: Pubkey,
+    /// Page index the stream was passed on
+    pub page_index: u32,
+    /// Timestamp of distribution
+    pub timestamp: i64,
 }
 
 /// Event emitted when creator receives remainder and day is closed
@@ -52,4 +184,226 @@ pub struct CreatorPayoutDayClosed {
     pub quote_mint: Pubkey,
     /// Timestamp when day closed
     pub timestamp: i64,
+    /// Total quote fees ever claimed for the vault
+    pub lifetime_claimed: u64,
+    /// Total quote ever paid to investors
+    pub lifetime_investor_distributed: u64,
+    /// Total quote ever paid to the creator
+    pub lifetime_creator_distributed: u64,
+    /// Number of distribution days closed, including this one
+    pub completed_days: u32,
+    /// Whether the authority force-closed the day instead of the last page
+    pub forced: bool,
+    /// Decimals of the quote mint, amounts are in its base units
+    pub quote_decimals: u8,
+}
+
+/// Event emitted when the creator's fee destination is changed
+#[event]
+pub struct CreatorAtaUpdated {
+    /// Previous creator quote ATA
+    pub old_creator_quote_ata: Pubkey,
+    /// New creator quote ATA
+    pub new_creator_quote_ata: Pubkey,
+    /// Timestamp of the update
+    pub timestamp: i64,
+}
+
+/// Event emitted when the emergency pause is toggled
+#[event]
+pub struct PausedUpdated {
+    /// Whether the program is now paused
+    pub paused: bool,
+    /// Authority that toggled the pause
+    pub authority: Pubkey,
+    /// Timestamp of the update
+    pub timestamp: i64,
+}
+
+/// Event emitted when the fast-path crank authority is set or cleared
+#[event]
+pub struct CrankAuthorityUpdated {
+    /// New crank authority, None restores the strictly gated crank
+    pub crank_authority: Option<Pubkey>,
+    /// Authority that made the update
+    pub authority: Pubkey,
+    /// Timestamp of the update
+    pub timestamp: i64,
+}
+
+/// Event emitted when the authority changes a vault's policy
+#[event]
+pub struct PolicyConfigUpdated {
+    /// Vault whose policy was updated
+    pub vault_seed: u64,
+    /// Names of the policy fields whose value changed
+    pub changed_fields: Vec<String>,
+    /// Authority that made the update
+    pub authority: Pubkey,
+    /// Timestamp of the update
+    pub timestamp: i64,
+}
+
+/// Event emitted when a distribution is previewed without moving tokens
+#[event]
+pub struct DistributionPreview {
+    /// Fees available for distribution (claimable plus carry-over)
+    pub total_fees: u64,
+    /// Total locked tokens across the provided streams
+    pub total_locked: u64,
+    /// Y0 total used for the locked fraction
+    pub y0_total: u64,
+    /// Locked fraction of Y0 in basis points
+    pub f_locked_bps: u64,
+    /// Eligible investor share in basis points
+    pub eligible_share_bps: u64,
+    /// Investor pool after the daily cap
+    pub investor_fee_quote: u64,
+    /// Payout per investor, in remaining accounts order; a wallet paid by several streams shows it on its first pair
+    pub investor_payouts: Vec<u64>,
+    /// Amount the creator would receive
+    pub creator_amount: u64,
+    /// Amount that would be carried over
+    pub carry_over: u64,
+    /// Timestamp of the preview
+    pub timestamp: i64,
+}
+
+/// Event emitted when stranded base-token fees are swept to the creator
+#[event]
+pub struct BaseFeesSwept {
+    /// Amount of base tokens swept
+    pub amount: u64,
+    /// Base mint
+    pub base_mint: Pubkey,
+    /// Creator's base ATA that received the tokens
+    pub creator_base_ata: Pubkey,
+    /// Vault seed of the swept treasury
+    pub vault_seed: u64,
+    /// Timestamp of the sweep
+    pub timestamp: i64,
+}
+
+/// Event emitted with the full distribution progress of a vault
+#[event]
+pub struct DistributionProgressSnapshot {
+    /// Vault seed of the progress account
+    pub vault_seed: u64,
+    /// Start of the current distribution period
+    pub last_distribution_ts: i64,
+    /// Investor payouts made in the current period
+    pub daily_distributed: u64,
+    /// Quote tokens carried over in the treasury
+    pub carry_over: u64,
+    /// Next page expected by the crank
+    pub page_cursor: u32,
+    /// Number of pages in the current period
+    pub total_pages: u32,
+    /// Whether the current period is closed
+    pub day_complete: bool,
+    /// Highest stream processed in the current period
+    pub last_processed_stream: Pubkey,
+    /// Timestamp of the last processed page
+    pub last_page_ts: i64,
+    /// Timestamp of the read
+    pub timestamp: i64,
+}
+
+/// Event emitted when a distribution progress account is migrated to the current layout
+#[event]
+pub struct DistributionProgressMigrated {
+    /// Vault seed of the progress account
+    pub vault_seed: u64,
+    /// Account size before the migration
+    pub old_len: u32,
+    /// Account size after the migration
+    pub new_len: u32,
+    /// Timestamp of the migration
+    pub timestamp: i64,
+}
+
+/// Event emitted when the global state account is migrated to the current layout
+#[event]
+pub struct GlobalStateMigrated {
+    /// Authority of the migrated global state, the creator who signed the migration
+    pub authority: Pubkey,
+    /// Account size before the migration
+    pub old_len: u32,
+    /// Account size after the migration
+    pub new_len: u32,
+    /// Timestamp of the migration
+    pub timestamp: i64,
+}
+
+/// Event emitted when the rent of a distribution progress account is prefunded
+#[event]
+pub struct CrankRentFunded {
+    /// Vault seed of the progress account
+    pub vault_seed: u64,
+    /// Account that paid the rent
+    pub funder: Pubkey,
+    /// Lamports added on top of the account's existing balance
+    pub top_up_lamports: u64,
+    /// Timestamp of the funding
+    pub timestamp: i64,
+}
+
+/// Event emitted when the honorary position NFT is transferred out of the position owner PDA
+#[event]
+pub struct PositionOwnershipTransferred {
+    /// Vault seed of the position
+    pub vault_seed: u64,
+    /// Honorary position
+    pub position: Pubkey,
+    /// Mint of the position NFT
+    pub position_nft_mint: Pubkey,
+    /// Previous owner, the position owner PDA
+    pub old_owner: Pubkey,
+    /// Wallet now owning the position NFT
+    pub new_owner: Pubkey,
+    /// Timestamp of the transfer
+    pub timestamp: i64,
+}
+
+/// Event emitted by distribute_fees when the quote mint has an unusual decimal configuration
+#[event]
+pub struct UnusualQuoteDecimals {
+    /// Quote mint
+    pub quote_mint: Pubkey,
+    /// Decimals of the quote mint
+    pub quote_decimals: u8,
+    /// Minimum payout of the crank, in quote base units
+    pub min_payout_lamports: u64,
+    /// Timestamp of the crank
+    pub timestamp: i64,
+}
+
+/// Event emitted when the authority flushes a vault's carry-over to the creator
+#[event]
+pub struct CarryOverFlushed {
+    /// Vault seed of the progress account
+    pub vault_seed: u64,
+    /// Carry-over paid to the creator
+    pub amount: u64,
+    /// Creator's quote ATA that received the carry-over
+    pub creator_quote_ata: Pubkey,
+    /// Quote mint
+    pub quote_mint: Pubkey,
+    /// Total quote ever paid to the creator, the flush included
+    pub lifetime_creator_distributed: u64,
+    /// Timestamp of the flush
+    pub timestamp: i64,
+}
+
+/// Event emitted when the authority closes old distribution receipts of a vault
+#[event]
+pub struct DistributionReceiptsClosed {
+    /// Vault seed of the receipts
+    pub vault_seed: u64,
+    /// Number of receipts closed
+    pub receipts_closed: u32,
+    /// Rent returned to the authority
+    pub lamports_reclaimed: u64,
+    /// Timestamp of the close
+    pub timestamp: i64,
 }
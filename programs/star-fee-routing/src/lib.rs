@@ -6,25 +6,80 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{self, Mint, Token, TokenAccount, Transfer},
+    token_2022::{
+        self,
+        spl_token_2022::{
+            extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+            state::Mint as Token2022Mint,
+        },
+    },
+    token_interface::{self, TokenInterface, TransferChecked},
 };
-use streamflow_sdk::state::Contract as StreamflowContract;
 
 declare_id!("45soP1GyzrULnWjAasDnp23T1yDZpkhPsQD6qQ98Ttdg");
 
 // DAMM V2 (CP-AMM) Program ID
-#[allow(dead_code)]
-const CP_AMM_PROGRAM_ID: &str = "cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG";
+pub const CP_AMM_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG");
 
+// SPL Memo (v2) Program ID
+pub const SPL_MEMO_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+#[cfg(all(not(target_os = "solana"), any(test, feature = "client")))]
+pub mod client;
+pub mod cp_amm;
 pub mod errors;
 pub mod events;
+mod math;
 pub mod state;
+pub mod streamflow;
 pub mod tests;
+pub mod vesting;
 
+pub use cp_amm::*;
 pub use errors::*;
 pub use events::*;
+use math::{bps_of, mul_div_floor, BPS_DENOMINATOR};
 pub use state::*;
+pub use streamflow::*;
+pub use vesting::*;
 
 const SECONDS_PER_DAY: i64 = 86400;
+const MIN_DISTRIBUTION_INTERVAL_SECONDS: i64 = 3600;
+
+/// DAMM V2 create_position instruction discriminator
+const CREATE_POSITION_DISCRIMINATOR: [u8; 8] = [48, 215, 197, 153, 96, 203, 180, 133];
+
+/// Remaining accounts per vault in initialize_honorary_positions_batch:
+/// [pool, position, position_nft_mint, position_nft_account, position_owner_pda, token_a_mint,
+///  quote_treasury, base_treasury, quote_treasury_authority]
+pub const BATCH_ACCOUNTS_PER_VAULT: usize = 9;
+
+/// Remaining accounts per additional position claimed by distribute_fees:
+/// [pool, position, position_nft_account, token_a_vault, token_b_vault]
+pub const ADDITIONAL_POSITION_ACCOUNTS: usize = 5;
+
+/// Maximum number of positions claimed alongside the honorary position in one crank
+pub const MAX_ADDITIONAL_POSITIONS: u8 = 4;
+
+/// Compute units kept in reserve for each position of a batch, the batch stops before running out
+pub const MIN_COMPUTE_UNITS_PER_POSITION: u64 = 80_000;
+
+/// Estimated compute units to read one investor's Streamflow stream on the accounting pass of a page
+pub const INVESTOR_ACCOUNTING_COMPUTE_UNITS: u64 = 15_000;
+
+/// Estimated compute units to validate and pay one investor on the payout pass of a page
+pub const INVESTOR_PAYOUT_COMPUTE_UNITS: u64 = 20_000;
+
+/// Compute units kept in reserve to settle a page: creator payout, progress commit, events and return data
+pub const PAGE_SETTLEMENT_COMPUTE_UNITS: u64 = 40_000;
+
+/// Maximum number of (stream, ATA) investor pairs accepted by one distribute_fees page
+pub const MAX_INVESTORS_PER_PAGE: usize = 32;
+
+/// Quote mints with more decimals can't represent one whole token in a u64
+pub const MAX_QUOTE_DECIMALS: u8 = 19;
 
 #[program]
 pub mod star_fee_routing {
@@ -32,18 +87,487 @@ pub mod star_fee_routing {
 
     /// @notice Initialize the global program state with creator configuration
     /// @dev Sets up the global state account that stores the creator's fee destination
+    /// @dev The payer becomes the authority for privileged configuration updates
     /// @param ctx The account context containing global_state, payer, and system_program
     /// @param creator_quote_ata The creator's Associated Token Account for receiving fee share
+    /// @param quote_mint The quote mint every distribution crank must use
     /// @return Result<()> indicating success or failure of initialization
-    pub fn initialize_global_state(ctx: Context<InitializeGlobalState>, creator_quote_ata: Pubkey) -> Result<()> {
+    pub fn initialize_global_state(
+        ctx: Context<InitializeGlobalState>,
+        creator_quote_ata: Pubkey,
+        quote_mint: Pubkey,
+    ) -> Result<()> {
         let global_state = &mut ctx.accounts.global_state;
 
         global_state.creator_quote_ata = creator_quote_ata;
+        global_state.quote_mint = quote_mint;
+        global_state.authority = ctx.accounts.payer.key();
+        global_state.paused = false;
+        global_state.crank_authority = None;
+        global_state.open_positions = 0;
+        global_state.untracked_positions = false;
+        global_state.version = GlobalState::VERSION;
         global_state.bump = ctx.bumps.global_state;
 
         Ok(())
     }
 
+    /// @notice Change the creator's fee destination ATA
+    /// @dev Only callable by the global state authority
+    /// @dev The new ATA must be a token account for the same mint as the current creator ATA
+    /// @param ctx The account context containing global_state, creator ATAs, and authority
+    /// @return Result<()> indicating success or failure of the update
+    pub fn update_creator_ata(ctx: Context<UpdateCreatorAta>) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        let global_state = &mut ctx.accounts.global_state;
+
+        let old_creator_quote_ata = global_state.creator_quote_ata;
+        global_state.creator_quote_ata = ctx.accounts.new_creator_quote_ata.key();
+
+        emit!(CreatorAtaUpdated {
+            old_creator_quote_ata,
+            new_creator_quote_ata: global_state.creator_quote_ata,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// @notice Pause or resume fee claiming and distribution
+    /// @dev Only callable by the global state authority, intended as an emergency stop during incidents
+    /// @param ctx The account context containing global_state and authority
+    /// @param paused Whether the program should be paused
+    /// @return Result<()> indicating success or failure of the update
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        let global_state = &mut ctx.accounts.global_state;
+
+        global_state.paused = paused;
+
+        emit!(PausedUpdated {
+            paused,
+            authority: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// @notice Set or clear the crank authority allowed to bypass the distribution interval
+    /// @dev Only callable by the global state authority, intended for incident recovery and testnet runs
+    /// @dev The permissionless crank stays strictly gated whatever this is set to
+    /// @param ctx The account context containing global_state and authority
+    /// @param crank_authority The fast-path signer, or None to disable the bypass
+    /// @return Result<()> indicating success or failure of the update
+    pub fn set_crank_authority(ctx: Context<SetCrankAuthority>, crank_authority: Option<Pubkey>) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        let global_state = &mut ctx.accounts.global_state;
+
+        global_state.crank_authority = crank_authority;
+
+        emit!(CrankAuthorityUpdated {
+            crank_authority,
+            authority: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// @notice Initialize the distribution policy for a vault
+    /// @dev Only callable by the global state authority
+    /// @dev Rejects investor_fee_share_bps above 10000 so misconfigurations fail loudly
+    /// @param ctx The account context containing global_state, policy_config, authority, and system_program
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param params The vault's policy values
+    /// @return Result<()> indicating success or failure of initialization
+    pub fn initialize_policy_config(
+        ctx: Context<InitializePolicyConfig>,
+        vault_seed: u64,
+        params: PolicyConfigParams,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        apply_policy_config(&mut ctx.accounts.policy_config, vault_seed, &params, ctx.bumps.policy_config)
+    }
+
+    /// @notice Change any subset of a vault's policy fields
+    /// @dev Only callable by the global state authority, between distribution days so every page of a day
+    /// @dev runs under the same policy; values go through the same validator as in initialize_policy_config
+    /// @param ctx The account context containing global_state, policy_config, distribution_progress and authority
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param update The fields to change, None leaves a field as it is
+    /// @return Result<()> - fails with DistributionInProgress while a day has pages left
+    pub fn update_policy_config(
+        ctx: Context<UpdatePolicyConfig>,
+        vault_seed: u64,
+        update: PolicyConfigUpdate,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        if is_distribution_day_open(&ctx.accounts.distribution_progress.to_account_info())? {
+            msg!("Finish or force-close the current distribution day before updating the policy");
+            return Err(FeeRoutingError::DistributionInProgress.into());
+        }
+
+        let changed_fields = apply_policy_update(&mut ctx.accounts.policy_config, &update)?;
+
+        msg!("Updated policy fields of vault {}: {:?}", vault_seed, changed_fields);
+        emit!(PolicyConfigUpdated {
+            vault_seed,
+            changed_fields,
+            authority: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// @notice Set the Merkle root of approved investor pairs for a vault
+    /// @dev Only callable by the global state authority, calling again replaces the root
+    /// @param ctx The account context containing global_state, investor_allowlist, authority, and system_program
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param merkle_root Root over sha256(stream_pubkey || investor_quote_ata) leaves
+    /// @return Result<()> indicating success or failure of the update
+    pub fn set_investor_allowlist(
+        ctx: Context<SetInvestorAllowlist>,
+        vault_seed: u64,
+        merkle_root: [u8; 32],
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        let investor_allowlist = &mut ctx.accounts.investor_allowlist;
+        investor_allowlist.vault_seed = vault_seed;
+        investor_allowlist.merkle_root = merkle_root;
+        investor_allowlist.bump = ctx.bumps.investor_allowlist;
+
+        Ok(())
+    }
+
+    /// @notice Create or update the raw lock of an investor whose tokens sit in an unsupported lockup
+    /// @dev Only callable by the global state authority, which attests the amounts; calling again replaces them
+    /// @dev The lock is paired with the recipient's quote ATA in distribute_fees like a Streamflow stream
+    /// @param ctx The account context containing global_state, raw_lock, authority, and system_program
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param recipient Wallet the locked tokens belong to
+    /// @param locked_amount Amount currently locked
+    /// @param deposited_amount Amount originally locked, counted towards Y0
+    /// @return Result<()> - fails with InvalidRawLock if more is locked than was deposited
+    pub fn set_raw_lock(
+        ctx: Context<SetRawLock>,
+        vault_seed: u64,
+        recipient: Pubkey,
+        locked_amount: u64,
+        deposited_amount: u64,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+        require_gte!(deposited_amount, locked_amount, FeeRoutingError::InvalidRawLock);
+
+        let raw_lock = &mut ctx.accounts.raw_lock;
+        raw_lock.vault_seed = vault_seed;
+        raw_lock.recipient = recipient;
+        raw_lock.locked_amount = locked_amount;
+        raw_lock.deposited_amount = deposited_amount;
+        raw_lock.bump = ctx.bumps.raw_lock;
+
+        msg!("Raw lock of {} for vault {}: {} locked of {}", recipient, vault_seed, locked_amount, deposited_amount);
+
+        Ok(())
+    }
+
+    /// @notice Register a Bonfida vesting account for a vault, with the wallet owning its destination account
+    /// @dev Only callable by the global state authority; registering the vesting again refreshes its entry
+    /// @dev Bonfida only names the base-mint account it releases into, so the crank pairs the vesting with a
+    /// @dev quote ATA of that account's owner, read here from the destination token account
+    /// @dev A vesting whose destination is changed later must be registered again before it is paid
+    /// @dev Bonfida zeroes a schedule once it is released and keeps no original total, so the authority registers
+    /// @dev the amount originally vested; it is the vesting's fixed contribution to Y0
+    /// @param ctx The account context containing the registry, the vesting and its destination token account
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param deposited_amount Amount originally vested, at least what the schedules still hold
+    /// @return Result<()> - fails with InvalidBonfidaDestination, InvalidBonfidaDeposit or BonfidaRegistryFull
+    pub fn register_bonfida_vesting(
+        ctx: Context<RegisterBonfidaVesting>,
+        vault_seed: u64,
+        deposited_amount: u64,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        let vesting = parse_bonfida_vesting(&ctx.accounts.vesting_account.try_borrow_data()?)?;
+        require_keys_eq!(
+            ctx.accounts.destination_token_account.key(),
+            vesting.destination_address,
+            FeeRoutingError::InvalidBonfidaDestination
+        );
+        require_gte!(deposited_amount, vesting.held_amount(), FeeRoutingError::InvalidBonfidaDeposit);
+        let registration = BonfidaRegistration {
+            vesting: ctx.accounts.vesting_account.key(),
+            destination_address: vesting.destination_address,
+            recipient: ctx.accounts.destination_token_account.owner,
+            deposited_amount,
+        };
+
+        let bonfida_registry = &mut ctx.accounts.bonfida_registry;
+        bonfida_registry.vault_seed = vault_seed;
+        bonfida_registry.bump = ctx.bumps.bonfida_registry;
+        match bonfida_registry.vestings.iter_mut().find(|entry| entry.vesting == registration.vesting) {
+            Some(entry) => *entry = registration,
+            None => {
+                require!(
+                    bonfida_registry.vestings.len() < BonfidaRegistry::MAX_VESTINGS,
+                    FeeRoutingError::BonfidaRegistryFull
+                );
+                bonfida_registry.vestings.push(registration);
+            }
+        }
+
+        msg!(
+            "Bonfida vesting {} registered for vault {}, paid to {} with {} deposited",
+            registration.vesting,
+            vault_seed,
+            registration.recipient,
+            deposited_amount
+        );
+
+        Ok(())
+    }
+
+    /// @notice Register the investors paid on one distribution page of a vault
+    /// @dev Only callable by the global state authority, calling again replaces the page's investors
+    /// @dev When the policy requires the registry, the crank pays exactly these pairs with these weights
+    /// @param ctx The account context containing global_state, investor_registry, authority, and system_program
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param page_index Distribution page the investors are paid on
+    /// @param page_count Number of registered pages, every page of the day must be registered
+    /// @param investors Investor pairs in strictly ascending stream order, at most MAX_INVESTORS_PER_PAGE
+    /// @return Result<()> indicating success or failure of the registration
+    pub fn register_investors(
+        ctx: Context<RegisterInvestors>,
+        vault_seed: u64,
+        page_index: u32,
+        page_count: u32,
+        investors: Vec<InvestorData>,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+        validate_registered_investors(page_index, page_count, &investors)?;
+
+        let investor_registry = &mut ctx.accounts.investor_registry;
+        investor_registry.vault_seed = vault_seed;
+        investor_registry.page_index = page_index;
+        investor_registry.page_count = page_count;
+        investor_registry.investors = investors;
+        investor_registry.bump = ctx.bumps.investor_registry;
+
+        msg!("Registered {} investors for page {} of {}", investor_registry.investors.len(), page_index, page_count);
+
+        Ok(())
+    }
+
+    /// @notice Split the creator remainder of a vault across several recipients
+    /// @dev Only callable by the global state authority
+    /// @dev Recipient ATAs must be passed after the investor pairs on every distribute_fees call
+    /// @param ctx The account context containing global_state, creator_split, authority, and system_program
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param recipients Recipient ATAs with their share in basis points, summing to 10000
+    /// @return Result<()> indicating success or failure of initialization
+    pub fn initialize_creator_split(
+        ctx: Context<InitializeCreatorSplit>,
+        vault_seed: u64,
+        recipients: Vec<CreatorRecipient>,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+        validate_creator_split(&recipients)?;
+
+        let creator_split = &mut ctx.accounts.creator_split;
+        creator_split.vault_seed = vault_seed;
+        creator_split.recipients = recipients;
+        creator_split.bump = ctx.bumps.creator_split;
+
+        Ok(())
+    }
+
+    /// @notice Prefund the rent of a vault's distribution progress account
+    /// @dev Creates the progress account if needed and tops its lamports up to the rent-exempt minimum,
+    /// @dev so permissionless crankers only pay transaction fees and are never blocked by rent
+    /// @param ctx The account context containing the distribution progress, funder, and system_program
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the funding
+    pub fn fund_crank_rent(ctx: Context<FundCrankRent>, vault_seed: u64) -> Result<()> {
+        let progress = &mut ctx.accounts.distribution_progress;
+        if progress.vault_seed == 0 {
+            init_distribution_progress(progress, vault_seed, ctx.bumps.distribution_progress);
+        }
+
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(DistributionProgress::LEN);
+        let current_lamports = ctx.accounts.distribution_progress.to_account_info().lamports();
+        let top_up_lamports = rent_exempt_minimum.saturating_sub(current_lamports);
+
+        if top_up_lamports > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.funder.to_account_info(),
+                        to: ctx.accounts.distribution_progress.to_account_info(),
+                    },
+                ),
+                top_up_lamports,
+            )?;
+        }
+
+        emit!(CrankRentFunded {
+            vault_seed,
+            funder: ctx.accounts.funder.key(),
+            top_up_lamports,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// @notice Migrate a vault's distribution progress account from the legacy layout to the current one
+    /// @dev Permissionless and idempotent: an account already at DistributionProgress::LEN only has an older
+    /// @dev schema version upgraded, one written by a newer program fails with UnsupportedStateVersion
+    /// @dev The account is reallocated with the payer topping up rent, existing fields are preserved and the
+    /// @dev fields added since are filled with defaults derived from them
+    /// @param ctx The account context containing the distribution progress, payer, and system_program
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the migration
+    pub fn migrate_distribution_progress(ctx: Context<MigrateDistributionProgress>, vault_seed: u64) -> Result<()> {
+        let progress_info = ctx.accounts.distribution_progress.to_account_info();
+        let old_len = progress_info.data_len();
+        if old_len == DistributionProgress::LEN {
+            let mut progress = {
+                let data = progress_info.try_borrow_data()?;
+                DistributionProgress::try_deserialize(&mut &data[..])?
+            };
+            if !needs_version_upgrade(progress.version, DistributionProgress::VERSION)? {
+                msg!("Distribution progress for vault {} is already migrated", vault_seed);
+                return Ok(());
+            }
+
+            msg!(
+                "Upgrading distribution progress for vault {} to version {}",
+                vault_seed,
+                DistributionProgress::VERSION
+            );
+            progress.version = DistributionProgress::VERSION;
+            let mut data = progress_info.try_borrow_mut_data()?;
+            progress.try_serialize(&mut &mut data[..])?;
+            return Ok(());
+        }
+
+        let progress = {
+            let data = progress_info.try_borrow_data()?;
+            migrate_legacy_progress(&data)?
+        };
+        require_eq!(progress.vault_seed, vault_seed, FeeRoutingError::VaultSeedMismatch);
+
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(DistributionProgress::LEN);
+        let top_up_lamports = rent_exempt_minimum.saturating_sub(progress_info.lamports());
+        if top_up_lamports > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: progress_info.clone(),
+                    },
+                ),
+                top_up_lamports,
+            )?;
+        }
+
+        progress_info.resize(DistributionProgress::LEN)?;
+        let mut data = progress_info.try_borrow_mut_data()?;
+        progress.try_serialize(&mut &mut data[..])?;
+
+        emit!(DistributionProgressMigrated {
+            vault_seed,
+            old_len: old_len as u32,
+            new_len: DistributionProgress::LEN as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// @notice Migrate the global state account from the legacy layout to the current one
+    /// @dev Idempotent: an account already at GlobalState::LEN only has an older schema version upgraded, one
+    /// @dev written by a newer program fails with UnsupportedStateVersion
+    /// @dev The legacy layout has no authority, so the owner of its creator quote ATA signs and becomes the
+    /// @dev authority; the quote mint is that ATA's mint. No account records the positions opened before, so
+    /// @dev the migrated deployment is marked as having untracked positions and can't be closed
+    /// @param ctx The account context containing the global state, creator quote ATA, creator, payer and system_program
+    /// @return Result<()> - fails with Unauthorized unless the owner of the recorded creator quote ATA signs
+    pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+        let global_state_info = ctx.accounts.global_state.to_account_info();
+        let old_len = global_state_info.data_len();
+        if old_len == GlobalState::LEN {
+            let mut global_state = {
+                let data = global_state_info.try_borrow_data()?;
+                GlobalState::try_deserialize(&mut &data[..])?
+            };
+            if !needs_version_upgrade(global_state.version, GlobalState::VERSION)? {
+                msg!("Global state is already migrated");
+                return Ok(());
+            }
+
+            msg!("Upgrading global state to version {}", GlobalState::VERSION);
+            global_state.version = GlobalState::VERSION;
+            let mut data = global_state_info.try_borrow_mut_data()?;
+            global_state.try_serialize(&mut &mut data[..])?;
+            return Ok(());
+        }
+
+        let global_state = {
+            let data = global_state_info.try_borrow_data()?;
+            migrate_legacy_global_state(&data, ctx.accounts.creator.key(), ctx.accounts.creator_quote_ata.mint)?
+        };
+        // Only the creator the deployment pays may take over the authority the legacy layout lacks
+        require_keys_eq!(
+            ctx.accounts.creator_quote_ata.key(),
+            global_state.creator_quote_ata,
+            FeeRoutingError::Unauthorized
+        );
+        require_keys_eq!(
+            ctx.accounts.creator_quote_ata.owner,
+            ctx.accounts.creator.key(),
+            FeeRoutingError::Unauthorized
+        );
+
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(GlobalState::LEN);
+        let top_up_lamports = rent_exempt_minimum.saturating_sub(global_state_info.lamports());
+        if top_up_lamports > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: global_state_info.clone(),
+                    },
+                ),
+                top_up_lamports,
+            )?;
+        }
+
+        global_state_info.resize(GlobalState::LEN)?;
+        let mut data = global_state_info.try_borrow_mut_data()?;
+        global_state.try_serialize(&mut &mut data[..])?;
+
+        emit!(GlobalStateMigrated {
+            authority: global_state.authority,
+            old_len: old_len as u32,
+            new_len: GlobalState::LEN as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     /// @notice Initialize a quote-only honorary fee position in a DAMM V2 pool
     /// @dev Creates a position via CPI to DAMM V2 that only accrues fees from the quote token
     /// @dev This is the core functionality for Work Package A - creating fee collection positions
@@ -60,27 +584,8 @@ pub mod star_fee_routing {
         let signer_seeds = &[&seeds[..]];
 
         // Call create_position instruction via CPI
-        anchor_lang::solana_program::program::invoke_signed(
-            &anchor_lang::solana_program::instruction::Instruction {
-                program_id: cp_amm_program.key(),
-                accounts: vec![
-                    AccountMeta::new_readonly(ctx.accounts.position_owner_pda.key(), true),
-                    AccountMeta::new(ctx.accounts.position_nft_mint.key(), true),
-                    AccountMeta::new(ctx.accounts.position_nft_account.key(), false),
-                    AccountMeta::new(ctx.accounts.payer.key(), true),
-                    AccountMeta::new_readonly(ctx.accounts.pool_authority.key(), false),
-                    AccountMeta::new_readonly(ctx.accounts.pool.key(), false),
-                    AccountMeta::new(ctx.accounts.position.key(), false),
-                    AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
-                    AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
-                    AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
-                    AccountMeta::new_readonly(ctx.accounts.associated_token_program.key(), false),
-                    AccountMeta::new_readonly(ctx.accounts.event_authority.key(), false),
-                    AccountMeta::new_readonly(cp_amm_program.key(), false),
-                ],
-                data: [48, 215, 197, 153, 96, 203, 180, 133].to_vec(), // create_position discriminator
-            },
-            &[
+        invoke_create_position(
+            [
                 ctx.accounts.position_owner_pda.to_account_info(),
                 ctx.accounts.position_nft_mint.to_account_info(),
                 ctx.accounts.position_nft_account.to_account_info(),
@@ -99,28 +604,220 @@ pub mod star_fee_routing {
         )?;
 
         // Emit event
-        emit!(HonoraryPositionInitialized {
-            position: ctx.accounts.position.key(),
-            position_owner: ctx.accounts.position_owner_pda.key(),
+        emit!(honorary_position_initialized(
+            ctx.accounts.position.key(),
+            ctx.accounts.position_nft_mint.key(),
+            ctx.accounts.position_nft_account.key(),
+            ctx.accounts.position_owner_pda.key(),
             vault_seed,
-            lower_tick: 0, // Not used in DAMM V2
-            upper_tick: 0, // Not used in DAMM V2
-            quote_mint: ctx.accounts.quote_mint.key(),
+            ctx.accounts.quote_mint.key(),
+        ));
+
+        track_position_opened(&mut ctx.accounts.global_state, 1)?;
+
+        Ok(())
+    }
+
+    /// @notice Set up a vault in one transaction: its policy config, treasuries and honorary position
+    /// @dev Combines initialize_policy_config and initialize_honorary_position, the global state must exist;
+    /// @dev only callable by the global state authority, and nothing is created unless every step succeeds
+    /// @param ctx The account context of both instructions, with the global state authority paying
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param params The vault's policy values, as in initialize_policy_config
+    /// @return Result<()> indicating success or failure of the vault setup
+    pub fn initialize_vault(ctx: Context<InitializeVault>, vault_seed: u64, params: PolicyConfigParams) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        apply_policy_config(&mut ctx.accounts.policy_config, vault_seed, &params, ctx.bumps.policy_config)?;
+
+        // Same quote-only guarantee as initialize_honorary_position
+        validate_quote_only_pool_data(&ctx.accounts.pool.try_borrow_data()?, &ctx.accounts.quote_mint.key())?;
+
+        let vault_seed_bytes = vault_seed.to_le_bytes();
+        let seeds = &[VAULT_SEED, &vault_seed_bytes, INVESTOR_FEE_POSITION_OWNER_SEED, &[ctx.bumps.position_owner_pda]];
+        invoke_create_position(
+            [
+                ctx.accounts.position_owner_pda.to_account_info(),
+                ctx.accounts.position_nft_mint.to_account_info(),
+                ctx.accounts.position_nft_account.to_account_info(),
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                ctx.accounts.pool.to_account_info(),
+                ctx.accounts.position.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.rent.to_account_info(),
+                ctx.accounts.associated_token_program.to_account_info(),
+                ctx.accounts.event_authority.to_account_info(),
+                ctx.accounts.cp_amm_program.to_account_info(),
+            ],
+            &[&seeds[..]],
+        )?;
+
+        emit!(honorary_position_initialized(
+            ctx.accounts.position.key(),
+            ctx.accounts.position_nft_mint.key(),
+            ctx.accounts.position_nft_account.key(),
+            ctx.accounts.position_owner_pda.key(),
+            vault_seed,
+            ctx.accounts.quote_mint.key(),
+        ));
+
+        track_position_opened(&mut ctx.accounts.global_state, 1)?;
+
+        Ok(())
+    }
+
+    /// @notice Initialize honorary positions for several vaults in one transaction
+    /// @dev Each vault is described by BATCH_ACCOUNTS_PER_VAULT remaining accounts, in vault_seeds order
+    /// @dev Stops before a position once fewer than MIN_COMPUTE_UNITS_PER_POSITION compute units remain;
+    /// @dev the remaining vaults can be sent again in a later batch
+    /// @param ctx The account context containing the shared DAMM V2 accounts, quote mint and payer
+    /// @param vault_seeds Unique identifier of each vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the batch
+    pub fn initialize_honorary_positions_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeHonoraryPositionsBatch<'info>>,
+        vault_seeds: Vec<u64>,
+    ) -> Result<()> {
+        if ctx.remaining_accounts.len() != vault_seeds.len() * BATCH_ACCOUNTS_PER_VAULT {
+            msg!(
+                "Expected {} accounts for {} vaults, got {}",
+                vault_seeds.len() * BATCH_ACCOUNTS_PER_VAULT,
+                vault_seeds.len(),
+                ctx.remaining_accounts.len()
+            );
+            return Err(FeeRoutingError::BatchAccountsMismatch.into());
+        }
+
+        let mut initialized = 0u32;
+        for (&vault_seed, vault_accounts) in
+            vault_seeds.iter().zip(ctx.remaining_accounts.chunks(BATCH_ACCOUNTS_PER_VAULT))
+        {
+            let remaining_units = anchor_lang::solana_program::compute_units::sol_remaining_compute_units();
+            if !has_compute_for_position(remaining_units) {
+                msg!("Stopping batch after {} positions, {} compute units left", initialized, remaining_units);
+                break;
+            }
+
+            let position_owner_bump = validate_batch_vault_accounts(vault_accounts, vault_seed, ctx.program_id)?;
+            let pool = &vault_accounts[0];
+            let position = &vault_accounts[1];
+            let position_nft_mint = &vault_accounts[2];
+            let position_nft_account = &vault_accounts[3];
+            let position_owner_pda = &vault_accounts[4];
+            let token_a_mint = &vault_accounts[5];
+            let quote_treasury = &vault_accounts[6];
+            let base_treasury = &vault_accounts[7];
+            let quote_treasury_authority = &vault_accounts[8];
+
+            // Same quote-only guarantee as the single-vault instruction
+            validate_quote_only_pool_data(&pool.try_borrow_data()?, &ctx.accounts.quote_mint.key())?;
+
+            // Treasuries are created idempotently, like init_if_needed on the single-vault instruction;
+            // the quote treasury goes through the quote mint's own token program so Token-2022 gets its extensions
+            for (treasury, mint, token_program) in [
+                (
+                    quote_treasury,
+                    ctx.accounts.quote_mint.to_account_info(),
+                    ctx.accounts.quote_token_program.to_account_info(),
+                ),
+                (base_treasury, token_a_mint.clone(), ctx.accounts.token_program.to_account_info()),
+            ] {
+                anchor_spl::associated_token::create_idempotent(CpiContext::new(
+                    ctx.accounts.associated_token_program.to_account_info(),
+                    anchor_spl::associated_token::Create {
+                        payer: ctx.accounts.payer.to_account_info(),
+                        associated_token: treasury.clone(),
+                        authority: quote_treasury_authority.clone(),
+                        mint,
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        token_program,
+                    },
+                ))?;
+            }
+
+            let vault_seed_bytes = vault_seed.to_le_bytes();
+            let seeds = &[VAULT_SEED, &vault_seed_bytes, INVESTOR_FEE_POSITION_OWNER_SEED, &[position_owner_bump]];
+            invoke_create_position(
+                [
+                    position_owner_pda.clone(),
+                    position_nft_mint.clone(),
+                    position_nft_account.clone(),
+                    ctx.accounts.payer.to_account_info(),
+                    ctx.accounts.pool_authority.to_account_info(),
+                    pool.clone(),
+                    position.clone(),
+                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                    ctx.accounts.rent.to_account_info(),
+                    ctx.accounts.associated_token_program.to_account_info(),
+                    ctx.accounts.event_authority.to_account_info(),
+                    ctx.accounts.cp_amm_program.to_account_info(),
+                ],
+                &[&seeds[..]],
+            )?;
+
+            emit!(honorary_position_initialized(
+                position.key(),
+                position_nft_mint.key(),
+                position_nft_account.key(),
+                position_owner_pda.key(),
+                vault_seed,
+                ctx.accounts.quote_mint.key(),
+            ));
+
+            initialized += 1;
+        }
+
+        track_position_opened(&mut ctx.accounts.global_state, initialized)?;
+
+        emit!(HonoraryPositionsBatchInitialized {
+            requested: vault_seeds.len() as u32,
+            initialized,
+            timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
     }
 
+    /// @notice Create the quote treasury of a vault ahead of its honorary position
+    /// @dev Permissionless and idempotent; the associated token program sizes the account for the quote mint's
+    /// @dev token program, so Token-2022 treasuries get the extensions their mint requires
+    /// @param ctx The account context containing the quote mint, treasury, treasury authority and payer
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the creation
+    pub fn initialize_quote_treasury(ctx: Context<InitializeQuoteTreasury>, vault_seed: u64) -> Result<()> {
+        msg!(
+            "Quote treasury {} ready for vault {} ({} bytes)",
+            ctx.accounts.quote_treasury.key(),
+            vault_seed,
+            ctx.accounts.quote_treasury.to_account_info().data_len()
+        );
+
+        Ok(())
+    }
+
     /// @notice Permissionless 24-hour fee distribution crank mechanism
     /// @dev Claims fees from DAMM V2 position and distributes to creator and investors pro-rata
     /// @dev This is the core functionality for Work Package B - automated fee distribution
     /// @dev Uses pagination to handle large numbers of investors across multiple transactions
+    /// @dev Any wallet may crank any page of a day; the payer only funds the progress account on its first crank
+    /// @dev and, when it is the crank authority, lets page 0 skip the distribution interval
+    /// @dev The investor fee share, daily cap, minimum payout and Y0 come from the policy, a caller can't move fees
+    /// @dev between creator and investors
+    /// @dev The additional positions' account groups lead the remaining accounts
+    /// @dev day_total_locked may only be 0 on a single-page day, and the day's pages may process at most it
+    /// @dev A page stops before the investor it can no longer afford in compute; the processed investors are
+    /// @dev settled, the day grows by one page and the return data reports more_pages_needed
     /// @param ctx The account context containing position, treasury, creator ATA, and program accounts
     /// @param page_index Index for pagination when processing multiple investors (0-based)
-    /// @param investor_fee_share_bps Basis points allocated to investors (e.g., 8000 = 80%)
-    /// @param daily_cap_lamports Optional daily distribution cap in lamports to prevent excessive payouts
-    /// @param min_payout_lamports Minimum payout threshold to prevent dust transactions
-    /// @param y0_total Total locked tokens across all Y0 investors for pro-rata calculation
+    /// @param total_pages Number of pages in the day, fixed on page 0, the creator is paid after the last page
+    /// @param investor_weights_bps Optional weight per investor pair in this page, empty means 10000 for everyone
+    /// @param claim_only Only claim fees into the treasury, no transfers and no day is started or closed
+    /// @param allowlist_proofs Merkle proof per investor pair in this page, required when the policy enables the allowlist
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param additional_positions Extra positions claimed with the honorary position on page 0 and claim-only calls
+    /// @param day_total_locked Locked amount of all the day's streams, read on page 0 and stored for every page
     /// @return Result<()> indicating success or failure of fee distribution
     #[allow(clippy::too_many_arguments)]
     pub fn distribute_fees<'info>(
@@ -128,203 +825,500 @@ pub mod star_fee_routing {
         _trade_amount: u64,
         _fee_percentage: u64, // Fixed-point value (e.g., 100 = 1%)
         page_index: u32,
-        investor_fee_share_bps: u32,
-        daily_cap_lamports: Option<u64>,
-        min_payout_lamports: u64,
-        y0_total: u64,
+        total_pages: u32,
+        investor_weights_bps: Vec<u16>,
+        claim_only: bool,
+        allowlist_proofs: Vec<Vec<[u8; 32]>>,
+        vault_seed: u64,
+        additional_positions: u8,
+        day_total_locked: u64,
     ) -> Result<()> {
+        // Emergency stop blocks both the claim and the payouts
+        require_not_paused(&ctx.accounts.global_state)?;
+
+        // The creator agreed to the policy's share, the crank takes no caller-supplied value for it
+        let investor_fee_share_bps = ctx.accounts.policy_config.investor_fee_share_bps;
+        // Defense in depth: the policy is validated at init, the crank re-checks the value it uses
+        validate_fee_share_bps(investor_fee_share_bps)?;
+        // The cap, dust threshold and Y0 decide the same split, so they come from the policy as well
+        let policy = &ctx.accounts.policy_config;
+        let daily_cap_lamports = policy.daily_cap_lamports;
+        let min_payout_lamports = policy.min_payout_lamports;
+        let y0_total = policy.y0_total;
+        // All amounts are raw base units of the quote mint, a cap scaled for other decimals is caught here
+        validate_daily_cap_scale(daily_cap_lamports, min_payout_lamports, ctx.accounts.quote_mint.decimals)?;
+        // A 0-decimal mint makes every base unit a whole token, thresholds and rounding count whole tokens
+        if validate_quote_decimals(ctx.accounts.quote_mint.decimals)? {
+            emit!(UnusualQuoteDecimals {
+                quote_mint: ctx.accounts.quote_mint.key(),
+                quote_decimals: ctx.accounts.quote_mint.decimals,
+                min_payout_lamports,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        // Additional positions lead the remaining accounts, creator split recipient ATAs, if any,
+        // follow the investor pairs
+        let (additional_position_accounts, remaining_accounts) =
+            split_additional_positions(ctx.remaining_accounts, additional_positions)?;
+        let creator_split_count = ctx.accounts.creator_split.as_ref().map_or(0, |split| split.recipients.len());
+        let (investor_accounts, creator_split_accounts) =
+            split_remaining_accounts(remaining_accounts, creator_split_count)?;
+        // Oversized pages are rejected before the claim, so a compute-exhausted page can't waste it
+        validate_investor_page_size(investor_accounts.len() / 2)?;
+        validate_investor_weights(&investor_weights_bps, investor_accounts.len() / 2)?;
+        let allowlist_root = resolve_allowlist_root(
+            ctx.accounts.policy_config.require_allowlist,
+            ctx.accounts.investor_allowlist.as_deref(),
+            &allowlist_proofs,
+            investor_accounts.len() / 2,
+        )?;
+
         let clock = Clock::get()?;
         let current_ts = clock.unix_timestamp;
 
         let progress = &mut ctx.accounts.distribution_progress;
 
-        // Initialize the progress account if it's new
+        // Initialize the progress account if it's new (fund_crank_rent may have created it already)
         if progress.vault_seed == 0 {
-            progress.vault_seed = 12345u64; // Placeholder vault seed
-            progress.last_distribution_ts = 0;
-            progress.daily_distributed = 0;
-            progress.carry_over = 0;
-            progress.page_cursor = 0;
-            progress.day_complete = false;
-            progress.bump = ctx.bumps.distribution_progress;
+            init_distribution_progress(progress, vault_seed, ctx.bumps.distribution_progress);
         }
 
-        // Check if this is the first distribution of a new day
-        let is_new_day = current_ts >= progress.last_distribution_ts + SECONDS_PER_DAY;
+        // A progress account recorded for another vault must never be cranked for this one
+        require_eq!(progress.vault_seed, vault_seed, FeeRoutingError::VaultSeedMismatch);
+
+        // Claim-only runs just the claim so operators can verify amounts, it never starts or closes a day
+        if claim_only {
+            validate_claim_only(progress)?;
+            let claimed_quote = claim_position_fees(
+                ctx.accounts,
+                additional_position_accounts,
+                ctx.bumps.position_owner_pda,
+                current_ts,
+            )?;
+            // A claim-only run exists to move fees into the treasury, claiming nothing is an error
+            validate_fees_available(claimed_quote, 0)?;
+
+            // Pre-claimed fees join the next day's investor pool through carry_over
+            let progress = &mut ctx.accounts.distribution_progress;
+            progress.carry_over =
+                progress.carry_over.checked_add(claimed_quote).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            progress.lifetime_claimed =
+                progress.lifetime_claimed.checked_add(claimed_quote).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+            set_distribution_result(&DistributionResult {
+                claimed_quote,
+                investor_total_distributed: 0,
+                creator_amount: 0,
+                investor_count: 0,
+                page_index,
+                day_complete: progress.day_complete,
+                investors_processed: 0,
+                more_pages_needed: false,
+            })?;
+            return Ok(());
+        }
+
+        // An interrupted day is resumed from its cursor, page 0 can't restart it and claim again
+        if page_index == 0 {
+            require_no_day_in_progress(progress)?;
+        }
 
-        if page_index == 0 && !is_new_day {
-            return Err(FeeRoutingError::TooEarlyForDistribution.into());
+        // Check if this is the first distribution of a new period (24h unless the policy says otherwise),
+        // the crank authority may open one early
+        let fast_path = page_index == 0 && is_crank_authority(&ctx.accounts.global_state, &ctx.accounts.payer.key());
+        let is_new_day = fast_path
+            || check_distribution_due(
+                page_index,
+                current_ts,
+                progress.last_distribution_ts,
+                ctx.accounts.policy_config.distribution_interval_seconds,
+            )?;
+        if fast_path {
+            msg!("Crank authority bypassing the distribution interval");
         }
 
-        // Reset progress for new day
+        // Reset progress for new day (carry_over is kept for the next investor pool)
         if is_new_day && page_index == 0 {
-            progress.last_distribution_ts = current_ts;
+            let distribution_interval_seconds = ctx.accounts.policy_config.distribution_interval_seconds;
+            progress.last_distribution_ts = align_to_epoch(
+                scheduled_distribution_ts(progress.last_distribution_ts, current_ts, distribution_interval_seconds)?,
+                ctx.accounts.policy_config.epoch_start_ts,
+                distribution_interval_seconds,
+            )?;
             progress.daily_distributed = 0;
-            progress.carry_over = 0;
             progress.page_cursor = 0;
+            progress.total_pages = total_pages;
             progress.day_complete = false;
+            progress.last_processed_stream = Pubkey::default();
+            progress.day_claimed = 0;
+            progress.day_investor_count = 0;
         }
 
-        // Validate page index
-        if page_index != progress.page_cursor {
-            return Err(FeeRoutingError::InvalidPageIndex.into());
-        }
+        // Validate page index, the page count is fixed for the whole day on page 0
+        validate_page_cursor(progress, page_index, total_pages, current_ts)?;
+        let is_last_page = page_index + 1 == progress.total_pages;
 
-        if progress.day_complete {
+        if progress.is_day_complete() {
             return Err(FeeRoutingError::DistributionAlreadyComplete.into());
         }
 
-        // Step 1: Claim fees from honorary position (only on first page)
-        let mut claimed_quote = 0u64;
-        if page_index == 0 {
-            // Call cp-amm claim_position_fee via CPI
-            let cp_amm_program = ctx.accounts.cp_amm_program.to_account_info();
+        // A fully vested vault pays nobody but the creator, its single page skips the investor loop entirely
+        let creator_only = ctx.accounts.policy_config.creator_only;
+        if creator_only {
+            validate_creator_only_page(page_index, total_pages, investor_accounts.len() / 2)?;
+        } else if page_index == 0 {
+            // A page 0 without streams would sweep the whole day to the creator, usually a forgotten attachment
+            validate_investor_set(investor_accounts.len() / 2, ctx.accounts.policy_config.allow_empty_investor_set)?;
+        }
 
-            let vault_seed_bytes = progress.vault_seed.to_le_bytes();
-            let seeds =
-                &[VAULT_SEED, &vault_seed_bytes, INVESTOR_FEE_POSITION_OWNER_SEED, &[ctx.bumps.position_owner_pda]];
-            let signer_seeds = &[&seeds[..]];
+        // A registry vault pays exactly the pairs registered for this page, caller-supplied pairs can't be injected
+        let require_investor_registry = ctx.accounts.policy_config.require_investor_registry;
+        let investor_pairs: Vec<(Pubkey, Pubkey)> =
+            investor_accounts.chunks_exact(2).map(|pair| (pair[0].key(), pair[1].key())).collect();
+        let investor_weights_bps = resolve_registered_weights(
+            require_investor_registry && !creator_only,
+            ctx.accounts.investor_registry.as_deref(),
+            page_index,
+            progress.total_pages,
+            &investor_pairs,
+            investor_weights_bps,
+        )?;
 
-            // Call claim_position_fee instruction
-            anchor_lang::solana_program::program::invoke_signed(
-                &anchor_lang::solana_program::instruction::Instruction {
-                    program_id: cp_amm_program.key(),
-                    accounts: vec![
-                        AccountMeta::new_readonly(ctx.accounts.pool_authority.key(), false),
-                        AccountMeta::new_readonly(ctx.accounts.pool.key(), false),
-                        AccountMeta::new(ctx.accounts.position.key(), false),
-                        AccountMeta::new(ctx.accounts.quote_treasury.key(), false),
-                        AccountMeta::new(ctx.accounts.quote_treasury.key(), false), // token_b_account same as quote
-                        AccountMeta::new(ctx.accounts.token_a_vault.key(), false),
-                        AccountMeta::new(ctx.accounts.token_b_vault.key(), false),
-                        AccountMeta::new(ctx.accounts.position_nft_account.key(), false),
-                        AccountMeta::new_readonly(ctx.accounts.token_a_mint.key(), false),
-                        AccountMeta::new_readonly(ctx.accounts.quote_mint.key(), false),
-                        AccountMeta::new_readonly(ctx.accounts.position_owner_pda.key(), true),
-                        AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
-                        AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
-                        AccountMeta::new_readonly(ctx.accounts.event_authority.key(), false),
-                        AccountMeta::new_readonly(cp_amm_program.key(), false),
-                    ],
-                    data: [180, 38, 154, 17, 133, 33, 162, 211].to_vec(), // claim_position_fee discriminator
-                },
-                &[
-                    ctx.accounts.pool_authority.to_account_info(),
-                    ctx.accounts.pool.to_account_info(),
-                    ctx.accounts.position.to_account_info(),
-                    ctx.accounts.quote_treasury.to_account_info(),
-                    ctx.accounts.quote_treasury.to_account_info(),
-                    ctx.accounts.token_a_vault.to_account_info(),
-                    ctx.accounts.token_b_vault.to_account_info(),
-                    ctx.accounts.position_nft_account.to_account_info(),
-                    ctx.accounts.token_a_mint.to_account_info(),
-                    ctx.accounts.quote_mint.to_account_info(),
-                    ctx.accounts.position_owner_pda.to_account_info(),
-                    ctx.accounts.token_program.to_account_info(),
-                    ctx.accounts.token_program.to_account_info(),
-                    ctx.accounts.event_authority.to_account_info(),
-                    cp_amm_program,
-                ],
-                signer_seeds,
+        // Step 1: Claim fees from honorary position (only on first page)
+        let claimed_this_page = page_claims_fees(page_index);
+        let mut claimed_quote = 0u64;
+        if claimed_this_page {
+            claimed_quote = claim_position_fees(
+                ctx.accounts,
+                additional_position_accounts,
+                ctx.bumps.position_owner_pda,
+                current_ts,
             )?;
-
-            // This enforces the bounty requirement: "Quote‑only enforcement: If any base fees
-            // are observed or a claim returns non‑zero base, the crank must fail deterministically"
-
-            // we only have quote token fees
-            if ctx.accounts.quote_treasury.amount == 0 {
-                msg!("No quote fees claimed - potential issue with fee collection");
-            } else {
-                msg!("Quote-only fee collection validated: {} tokens claimed", ctx.accounts.quote_treasury.amount);
-            }
-            claimed_quote = ctx.accounts.quote_treasury.amount;
-
-            if claimed_quote == 0 {
-                return Err(FeeRoutingError::NoFeesAvailable.into());
-            }
-
-            // Double-check: Ensure we only have quote token fees
-            msg!("Fee claim validation passed:");
-            msg!("  Quote token fees claimed: {}", claimed_quote);
-            msg!("  Base token fees claimed: 0 ✓");
-            msg!("  Quote-only requirement satisfied ✓");
-
-            emit!(QuoteFeesClaimed {
-                amount_claimed: claimed_quote,
-                quote_mint: ctx.accounts.quote_mint.key(),
-                timestamp: current_ts,
-            });
+            // Carried dust from earlier days is still distributed when the position had nothing new to claim
+            validate_fees_available(claimed_quote, ctx.accounts.distribution_progress.carry_over)?;
         }
+        let progress = &mut ctx.accounts.distribution_progress;
 
         // Step 2: Query total locked tokens from Streamflow contracts
         // Remaining accounts should be passed as: [streamflow_stream_1, investor_ata_1, streamflow_stream_2,
-        // investor_ata_2, ...]
+        // investor_ata_2, ...] sorted by strictly ascending stream pubkey across all pages of the day
         let mut total_locked = 0u64;
+        let mut total_weighted_locked = 0u64;
         let mut total_y0_amount = 0u64;
+        let mut page_weighted_locked = Vec::with_capacity(investor_accounts.len() / 2);
+        // Each position is read once here and reused by the payout pass, None for a skipped stream
+        let mut page_positions: Vec<Option<LockedPosition>> = Vec::with_capacity(investor_accounts.len() / 2);
+        let mut last_processed_stream = progress.last_processed_stream;
+        let mut investors_processed = 0usize;
+        let min_locked_to_participate = ctx.accounts.policy_config.min_locked_to_participate;
+        let creator_sweep_threshold = ctx.accounts.policy_config.creator_sweep_threshold;
+        let skip_invalid_streams = ctx.accounts.policy_config.skip_invalid_streams;
+        let bonfida_registry = ctx.accounts.bonfida_registry.as_deref();
 
         // Process pairs of accounts: (streamflow_contract, investor_ata)
-        for chunk in ctx.remaining_accounts.chunks(2) {
+        for (investor_index, chunk) in investor_accounts.chunks(2).enumerate() {
             if chunk.len() != 2 {
                 continue; // Skip incomplete pairs
             }
 
+            // The first investor always runs so every page makes progress, later ones end the page early
+            // once the compute left can't cover their payout plus the payouts of everyone already counted
+            if investor_index > 0 {
+                let remaining_units = anchor_lang::solana_program::compute_units::sol_remaining_compute_units();
+                if !has_compute_for_investor(remaining_units, investor_index as u64) {
+                    msg!(
+                        "Stopping page after {} investors, {} compute units left",
+                        investors_processed,
+                        remaining_units
+                    );
+                    break;
+                }
+            }
+
             let streamflow_account = &chunk[0];
-            let _investor_ata = &chunk[1]; // Will be used for transfers later
+            let investor_ata = &chunk[1];
 
-            // Query locked amount from this Streamflow contract
-            let locked_amount = get_locked_amount_from_streamflow(streamflow_account)?;
-            total_locked = total_locked.checked_add(locked_amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            // Only pairs proven against the allowlist may take part when the policy requires it
+            if let Some(root) = &allowlist_root {
+                if !verify_allowlist_proof(
+                    root,
+                    &streamflow_account.key(),
+                    &investor_ata.key(),
+                    &allowlist_proofs[investor_index],
+                ) {
+                    msg!("Investor pair {} / {} is not allowlisted", streamflow_account.key(), investor_ata.key());
+                    return Err(FeeRoutingError::InvestorNotAllowlisted.into());
+                }
+            }
+
+            // Ascending order makes it impossible to count a stream twice within the day
+            validate_stream_order(&last_processed_stream, &streamflow_account.key())?;
+            last_processed_stream = streamflow_account.key();
+
+            // A stream that can't be read counts as nothing locked when the policy skips it, so one bad
+            // account doesn't block the rest of the day; the pair is also passed over in the payout loop
+            let stream_skipped = is_skipped_stream(streamflow_account, vault_seed, skip_invalid_streams);
+            if stream_skipped {
+                msg!("Skipping stream {} that failed to deserialize", streamflow_account.key());
+                emit!(StreamSkipped {
+                    stream: streamflow_account.key(),
+                    investor_ata: investor_ata.key(),
+                    page_index,
+                    timestamp: current_ts,
+                });
+            }
+
+            // Query the locked amount from the stream's vesting provider, dust positions don't take part
+            let locked_position = if stream_skipped {
+                None
+            } else {
+                Some(load_locked_position(streamflow_account, vault_seed, current_ts, bonfida_registry)?)
+            };
+            let locked_amount = locked_position
+                .map_or(0, |position| participating_locked_amount(position.locked_amount, min_locked_to_participate));
+            total_locked = total_locked.checked_add(locked_amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+            let weighted_locked =
+                calculate_weighted_locked(locked_amount, investor_weight_bps(&investor_weights_bps, investor_index))?;
+            total_weighted_locked =
+                total_weighted_locked.checked_add(weighted_locked).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            page_weighted_locked.push(weighted_locked);
+            page_positions.push(locked_position);
 
             // For Y0 calculation, we need the original deposited amount
-            let stream_data = &streamflow_account.data.borrow()[..];
-            if let Ok(contract) = StreamflowContract::try_from_slice(stream_data) {
+            if let Some(position) = locked_position {
                 total_y0_amount = total_y0_amount
-                    .checked_add(contract.ix.net_amount_deposited)
+                    .checked_add(position.deposited_amount)
                     .ok_or(FeeRoutingError::ArithmeticOverflow)?;
             }
+
+            investors_processed = investor_index + 1;
         }
 
+        progress.last_processed_stream = last_processed_stream;
+
+        // Investors cut off by the compute limit move to an extra page, only the processed ones are paid here
+        let more_pages_needed = investors_processed < investor_accounts.len() / 2;
+        // A registered page can't be split, its tail would have no registry page to be paid from
+        if more_pages_needed && require_investor_registry {
+            msg!("Registered page {} stopped after {} investors", page_index, investors_processed);
+            return Err(FeeRoutingError::RegistryPageTooLarge.into());
+        }
+        // A page 0 sent as the whole day has no caller total, when compute cuts it short the streams left for the
+        // extra page are still read into the day's snapshot so every page is sized against the same day
+        let snapshots_page_tail =
+            page_index == 0 && more_pages_needed && progress.total_pages == 1 && day_total_locked == 0 && !creator_only;
+        let (tail_locked, tail_y0_amount) = if snapshots_page_tail {
+            sum_page_tail_locked(
+                &investor_accounts[investors_processed * 2..],
+                vault_seed,
+                current_ts,
+                bonfida_registry,
+                skip_invalid_streams,
+                min_locked_to_participate,
+            )?
+        } else {
+            (0, 0)
+        };
+        let investor_accounts = &investor_accounts[..investors_processed * 2];
+        let is_last_page = is_last_page && !more_pages_needed;
+
         msg!("Distribution calculation:");
         msg!("  - Total currently locked: {}", total_locked);
         msg!("  - Total Y0 deposited: {}", total_y0_amount);
-        msg!("  - Number of streams: {}", ctx.remaining_accounts.len() / 2);
-
-        if total_locked == 0 {
-            // All tokens unlocked - send everything to creator
-            if page_index == 0 && claimed_quote > 0 {
-                // Set day complete first to avoid borrow issue
-                progress.day_complete = true;
-                transfer_to_creator(&ctx, claimed_quote, current_ts)?;
-            }
-            return Ok(());
+        msg!("  - Number of streams: {}", investor_accounts.len() / 2);
+
+        // Use dynamically queried Y0 total instead of parameter for more accurate calculation; a page of a
+        // multi-page day only sees its own streams, so there the configured Y0 of the whole day is used
+        // A missing Y0 is a configuration error, fail before any tokens move
+        let multi_page_day = progress.total_pages > 1 || more_pages_needed;
+        // Page 0 resolves the day's totals as the caller sent it, a cut-off single page saw all of its streams
+        let requested_multi_page_day = progress.total_pages > 1;
+        let day_y0_amount = total_y0_amount.checked_add(tail_y0_amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+        // Creator-only days have no investors, so there is no Y0 to resolve
+        let y0_total_actual = if creator_only {
+            0
+        } else {
+            resolve_day_y0_total(day_y0_amount, y0_total, multi_page_day && !snapshots_page_tail)?
+        };
+
+        // Page 0 fixes the day's denominators so every page splits the same pool pro-rata,
+        // the streams of each page must stay within the snapshot
+        if page_index == 0 {
+            let day_locked_seen = total_locked.checked_add(tail_locked).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            progress.day_total_locked =
+                snapshot_day_total_locked(day_total_locked, day_locked_seen, requested_multi_page_day)?;
+            progress.day_y0_total = y0_total_actual;
+            progress.day_locked_processed = 0;
         }
+        // The day's processed locked amount is tracked either way, it's the receipt's total without a snapshot
+        let has_day_snapshot = progress.day_total_locked > 0;
+        progress.day_locked_processed =
+            progress.day_locked_processed.checked_add(total_locked).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+        if has_day_snapshot && progress.day_locked_processed > progress.day_total_locked {
+            msg!(
+                "Streams processed today lock {}, above the day's snapshot of {}",
+                progress.day_locked_processed,
+                progress.day_total_locked
+            );
+            return Err(FeeRoutingError::InvalidLockedSnapshot.into());
+        }
+        // Streams keep unlocking between page 0 and the last page, so the pages may add up to less than the
+        // snapshot; the investor pool of the shortfall is carried over with the day's investor dust
+        if has_day_snapshot && is_last_page && progress.day_locked_processed < progress.day_total_locked {
+            msg!(
+                "Streams processed today lock {}, {} below the day's snapshot",
+                progress.day_locked_processed,
+                progress.day_total_locked - progress.day_locked_processed
+            );
+        }
+        let (f_locked_total, y0_total_actual) = if has_day_snapshot {
+            (progress.day_total_locked, progress.day_y0_total)
+        } else {
+            (total_locked, y0_total_actual)
+        };
 
-        // Use dynamically queried Y0 total instead of parameter for more accurate calculation
-        let y0_total_actual = if total_y0_amount > 0 { total_y0_amount } else { y0_total };
-
-        // Step 3: Calculate investor share
-        let f_locked = (total_locked as u128)
-            .checked_mul(10000u128)
-            .ok_or(FeeRoutingError::ArithmeticOverflow)?
-            .checked_div(y0_total_actual as u128)
-            .ok_or(FeeRoutingError::ArithmeticOverflow)? as u64;
+        let total_fees_for_distribution =
+            calculate_total_fees_for_distribution(page_index, claimed_quote, progress.carry_over)?;
+        // Every page sizes its investor pool from the whole day's fees, not what earlier pages left over
+        let day_fee_pool = calculate_day_fee_pool(total_fees_for_distribution, progress.daily_distributed)?;
 
-        let eligible_investor_share_bps = std::cmp::min(investor_fee_share_bps as u64, f_locked);
+        if total_locked == 0 {
+            // Creator-only mode always lands here, its pages count no locked tokens
+            // All tokens unlocked - the day's fees (claim and carry-over) go to the creator on any page; the amount
+            // is computed, not read from the treasury balance, so unaccounted treasury dust is never swept along.
+            // A page cut short by compute can't know that yet, so it pays nobody and keeps the pool for the next page.
+            // A snapshot day's streams may have fully unlocked since page 0, its investor pool stays with the day
+            let reserves_day_pool = has_day_snapshot && !creator_only;
+            let (creator_amount, carry_over) = if more_pages_needed {
+                (0, total_fees_for_distribution)
+            } else if reserves_day_pool {
+                let pool = size_page_investor_pool(
+                    &ctx.accounts.policy_config,
+                    day_fee_pool,
+                    0,
+                    f_locked_total,
+                    y0_total_actual,
+                    has_day_snapshot,
+                    progress.daily_distributed,
+                )?;
+                let (creator_amount, carry_over) = calculate_page_settlement(
+                    total_fees_for_distribution,
+                    pool.day_investor_pool_left,
+                    0,
+                    is_last_page,
+                )?;
+                if is_last_page {
+                    defer_dust_creator_amount(creator_amount, carry_over, creator_sweep_threshold)?
+                } else {
+                    (creator_amount, carry_over)
+                }
+            } else {
+                let (creator_amount, carry_over) =
+                    calculate_creator_amount_and_carry_over(total_fees_for_distribution, 0, 0)?;
+                defer_dust_creator_amount(creator_amount, carry_over, creator_sweep_threshold)?
+            };
+            // Without a snapshot a page with nothing locked settles the whole day
+            let day_complete = if reserves_day_pool { is_last_page } else { !more_pages_needed };
+
+            // Move tokens before committing progress so a failed transfer never leaves the cursor advanced
+            if day_complete {
+                transfer_to_creator(
+                    &ctx.accounts.creator_payout_accounts(ctx.bumps.quote_treasury_authority),
+                    creator_split_accounts,
+                    creator_amount,
+                )?;
+            }
 
-        let total_fees_for_distribution =
-            if page_index == 0 { claimed_quote + progress.carry_over } else { progress.carry_over };
+            let progress = &mut ctx.accounts.distribution_progress;
+            commit_page_progress(
+                progress,
+                &PageOutcome {
+                    claimed_quote,
+                    investor_distributed: 0,
+                    investor_count: 0,
+                    creator_amount,
+                    carry_over,
+                    page_cursor: page_index + 1,
+                    day_complete,
+                    more_pages_needed,
+                    page_ts: current_ts,
+                },
+            )?;
+            if day_complete {
+                emit_creator_payout_day_closed(
+                    progress,
+                    ctx.accounts.quote_mint.key(),
+                    ctx.accounts.quote_mint.decimals,
+                    creator_amount,
+                    current_ts,
+                    false,
+                );
+                if ctx.accounts.policy_config.emit_memo {
+                    log_distribution_memo(
+                        ctx.accounts.memo_program.as_deref(),
+                        &distribution_memo(progress, 0, creator_amount),
+                    )?;
+                }
+                write_distribution_receipt(
+                    ctx.accounts.distribution_receipt.as_deref_mut(),
+                    progress,
+                    creator_amount,
+                    y0_total_actual,
+                    current_ts,
+                );
+            }
 
-        let investor_fee_quote = total_fees_for_distribution
-            .checked_mul(eligible_investor_share_bps)
-            .ok_or(FeeRoutingError::ArithmeticOverflow)?
-            .checked_div(10000)
-            .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            set_distribution_result(&DistributionResult {
+                claimed_quote,
+                investor_total_distributed: 0,
+                creator_amount,
+                investor_count: 0,
+                page_index,
+                day_complete,
+                investors_processed: investors_processed as u32,
+                more_pages_needed,
+            })?;
+            return Ok(());
+        }
 
-        // Apply daily cap
-        let remaining_daily_cap =
-            if let Some(cap) = daily_cap_lamports { cap.saturating_sub(progress.daily_distributed) } else { u64::MAX };
+        // Step 3: Size the page's investor pool, the same way preview_distribution does
+        let PageInvestorPool { f_locked, uncapped_investor_fee_quote, investor_fee_quote, day_investor_pool_left } =
+            size_page_investor_pool(
+                &ctx.accounts.policy_config,
+                day_fee_pool,
+                total_locked,
+                f_locked_total,
+                y0_total_actual,
+                has_day_snapshot,
+                progress.daily_distributed,
+            )?;
+        if let Some(cap) = daily_cap_lamports {
+            if investor_fee_quote < uncapped_investor_fee_quote {
+                msg!(
+                    "Daily cap {} clipped the investor pool from {} to {}",
+                    cap,
+                    uncapped_investor_fee_quote,
+                    investor_fee_quote
+                );
+                emit!(DailyCapReached {
+                    uncapped_amount: uncapped_investor_fee_quote,
+                    daily_cap: cap,
+                    clipped_amount: investor_fee_quote,
+                    page_index,
+                    timestamp: current_ts,
+                });
+            }
+        }
 
-        let investor_fee_quote = std::cmp::min(investor_fee_quote, remaining_daily_cap);
+        // Apportion the page's pool across its investors with the vault's rounding policy
+        let investor_shares = allocate_investor_shares(
+            &page_weighted_locked,
+            investor_fee_quote,
+            total_weighted_locked,
+            ctx.accounts.policy_config.rounding_mode,
+        )?;
 
         // Step 4: Distribute fees to investors pro-rata based on locked amounts
         let vault_seed = progress.vault_seed;
@@ -332,10 +1326,26 @@ pub mod star_fee_routing {
         let signer_seeds = &[&seeds[..]];
 
         let mut total_distributed = 0u64;
+        let mut transfer_fees = 0u64;
         let mut investor_count = 0u32;
+        let quote_mint_info = ctx.accounts.quote_mint.to_account_info();
+        let quote_decimals = ctx.accounts.quote_mint.decimals;
+        let epoch = Clock::get()?.epoch;
+        let skip_unpayable_investors = ctx.accounts.policy_config.skip_unpayable_investors;
+        let per_investor_daily_cap = ctx.accounts.policy_config.per_investor_daily_cap;
+        // The per-investor cap covers a wallet's whole day, a day of several pages needs the ledger of what
+        // earlier pages paid each wallet
+        let mut investor_cap_ledger = ctx.accounts.investor_cap_ledger.as_deref_mut();
+        if per_investor_daily_cap.is_some() && multi_page_day && investor_cap_ledger.is_none() {
+            msg!("Per-investor cap on a day of {} pages needs the investor cap ledger", progress.total_pages);
+            return Err(FeeRoutingError::MissingInvestorCapLedger.into());
+        }
+        let day = progress.completed_days;
 
-        // Process pairs of accounts: (streamflow_contract, investor_ata)
-        for chunk in ctx.remaining_accounts.chunks(2) {
+        // Process pairs of accounts: (streamflow_contract, investor_ata), summing shares per ATA so an investor
+        // with several streams paying one wallet is treated as one investor
+        let mut wallet_payouts: Vec<WalletPayout> = Vec::with_capacity(investors_processed);
+        for (investor_index, chunk) in investor_accounts.chunks(2).enumerate() {
             if chunk.len() != 2 {
                 continue; // Skip incomplete pairs
             }
@@ -343,357 +1353,4362 @@ pub mod star_fee_routing {
             let streamflow_account = &chunk[0];
             let investor_ata = &chunk[1];
 
+            // Skipped streams had no locked amount and already emitted StreamSkipped
+            let Some(locked_position) = page_positions[investor_index] else {
+                continue;
+            };
+
+            // Reject pairs where the ATA is not the stream recipient's, so payouts can't be redirected
+            validate_investor_ata(&locked_position, investor_ata)?;
+
             // Query locked amount for this specific investor
-            let investor_locked = get_locked_amount_from_streamflow(streamflow_account)?;
+            let investor_locked = participating_locked_amount(locked_position.locked_amount, min_locked_to_participate);
 
             if investor_locked == 0 {
-                continue; // Skip investors with no locked tokens
+                continue; // Skip investors with no locked tokens or below the participation threshold
             }
 
-            // Calculate this investor's share: (investor_locked / total_locked) * investor_fee_quote
-            let investor_share = (investor_locked as u128)
-                .checked_mul(investor_fee_quote as u128)
-                .ok_or(FeeRoutingError::ArithmeticOverflow)?
-                .checked_div(total_locked as u128)
-                .ok_or(FeeRoutingError::ArithmeticOverflow)? as u64;
+            // This investor's share: (locked * weight / sum(locked * weight)) * investor_fee_quote, rounded per policy
+            let investor_share = investor_shares[investor_index];
+            add_wallet_payout(
+                &mut wallet_payouts,
+                investor_ata.key(),
+                investor_index * 2 + 1,
+                streamflow_account.key(),
+                investor_locked,
+                investor_share,
+            )?;
+        }
+
+        // Pay each wallet once, so the per-investor cap, the payout minimum and the payout event see its combined share
+        for wallet in &wallet_payouts {
+            let investor_ata = &investor_accounts[wallet.ata_index];
+            let investor_locked = wallet.locked_amount;
+            let investor_share = wallet.share;
+
+            // Re-check the cap per transfer so cumulative payouts can never exceed it, even with rounding;
+            // no single investor gets more than the per-investor cap, the excess stays in the treasury as carry_over
+            let distributed_today =
+                progress.daily_distributed.checked_add(total_distributed).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            let paid_today =
+                investor_cap_ledger.as_deref().map_or(0, |ledger| ledger.paid_on(day, &investor_ata.key()));
+            let (uncapped_investor_share, investor_share) = cap_wallet_share(
+                investor_share,
+                distributed_today,
+                paid_today,
+                daily_cap_lamports,
+                per_investor_daily_cap,
+            );
+            if investor_share < uncapped_investor_share {
+                msg!(
+                    "Per-investor cap clipped payout to {} from {} to {}",
+                    investor_ata.key(),
+                    uncapped_investor_share,
+                    investor_share
+                );
+                emit!(InvestorCapReached {
+                    investor_ata: investor_ata.key(),
+                    stream: wallet.stream,
+                    uncapped_amount: uncapped_investor_share,
+                    clipped_amount: investor_share,
+                    page_index,
+                    timestamp: current_ts,
+                });
+            }
 
             if investor_share < min_payout_lamports {
                 msg!("Skipping investor payout below minimum threshold: {} < {}", investor_share, min_payout_lamports);
                 continue;
             }
 
-            // Transfer tokens to investor
+            // A failed CPI can't be caught on Solana, so unpayable ATAs are detected before the transfer;
+            // the unpaid share is not counted as distributed and stays in the treasury as carry_over
+            if skip_unpayable_investors {
+                if let Some(reason) = payout_skip_reason(investor_ata, &ctx.accounts.quote_mint.key()) {
+                    msg!(
+                        "Skipping payout of {} to unpayable investor ATA {}: {:?}",
+                        investor_share,
+                        investor_ata.key(),
+                        reason
+                    );
+                    emit!(PayoutSkipped {
+                        investor_ata: investor_ata.key(),
+                        stream: wallet.stream,
+                        amount: investor_share,
+                        reason,
+                        page_index,
+                        timestamp: current_ts,
+                    });
+                    continue;
+                }
+            }
+
+            // Send the transfer-fee-inclusive amount so the investor receives investor_share net
+            let gross_amount = gross_amount_for_net(&quote_mint_info, investor_share, epoch)?;
             let transfer_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                ctx.accounts.quote_token_program.to_account_info(),
+                TransferChecked {
                     from: ctx.accounts.quote_treasury.to_account_info(),
+                    mint: quote_mint_info.clone(),
                     to: investor_ata.to_account_info(),
                     authority: ctx.accounts.quote_treasury_authority.to_account_info(),
                 },
             );
 
-            token::transfer(transfer_ctx.with_signer(signer_seeds), investor_share)?;
+            token_interface::transfer_checked(transfer_ctx.with_signer(signer_seeds), gross_amount, quote_decimals)?;
 
             total_distributed =
                 total_distributed.checked_add(investor_share).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            let transfer_fee = gross_amount.checked_sub(investor_share).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            transfer_fees = transfer_fees.checked_add(transfer_fee).ok_or(FeeRoutingError::ArithmeticOverflow)?;
             investor_count += 1;
+            if let Some(ledger) = investor_cap_ledger.as_deref_mut() {
+                record_wallet_payout(ledger, vault_seed, day, investor_ata.key(), investor_share)?;
+            }
+
+            emit!(InvestorPayout {
+                investor_ata: investor_ata.key(),
+                stream: wallet.stream,
+                locked_amount: investor_locked,
+                stream_count: wallet.stream_count,
+                payout_amount: investor_share,
+                page_index,
+                timestamp: current_ts,
+            });
 
             msg!("Distributed {} quote tokens to investor (locked: {})", investor_share, investor_locked);
         }
 
-        emit!(InvestorPayoutPage { page_index, investor_count, total_distributed, timestamp: current_ts });
+        emit!(InvestorPayoutPage {
+            page_index,
+            investor_count,
+            total_distributed,
+            timestamp: current_ts,
+            claimed_this_page,
+            total_locked,
+            eligible_share_bps: eligible_investor_share_bps(investor_fee_share_bps, f_locked),
+        });
 
-        progress.daily_distributed =
+        let daily_distributed =
             progress.daily_distributed.checked_add(total_distributed).ok_or(FeeRoutingError::ArithmeticOverflow)?;
 
-        // Send remainder to creator and complete the day
-        let treasury_balance = ctx.accounts.quote_treasury.amount;
-        let creator_amount = treasury_balance; // All remaining balance goes to creator
-
-        // Set completion status first
-        progress.day_complete = true;
-
-        if creator_amount > 0 {
-            transfer_to_creator(&ctx, creator_amount, current_ts)?;
+        // Hard ceiling: cumulative investor payouts for the day must never exceed the cap
+        if let Some(cap) = daily_cap_lamports {
+            if daily_distributed > cap {
+                return Err(FeeRoutingError::DailyCapExceeded.into());
+            }
         }
 
-        Ok(())
-    }
-}
+        // Investor dust of every page stays in the treasury as carry_over, the creator share is only swept after
+        // the last page
+        let (creator_amount, carry_over) = calculate_page_settlement(
+            total_fees_for_distribution,
+            day_investor_pool_left,
+            total_distributed,
+            is_last_page,
+        )?;
 
-/// @notice Transfer quote token fees to the creator's Associated Token Account
-/// @dev Uses program PDA authority to transfer from quote treasury to creator ATA
-/// @dev Emits CreatorFeePaid event for transparency and tracking
-/// @param ctx The distribution context containing treasury and creator accounts
-/// @param amount The amount of quote tokens to transfer to creator (in token's base units)
-/// @param timestamp Current Unix timestamp for event logging
-/// @return Result<()> indicating success or failure of the transfer
-fn transfer_to_creator(ctx: &Context<DistributeFees>, amount: u64, timestamp: i64) -> Result<()> {
-    let transfer_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.quote_treasury.to_account_info(),
-            to: ctx.accounts.creator_quote_ata.to_account_info(),
-            authority: ctx.accounts.quote_treasury_authority.to_account_info(),
-        },
-    );
+        // Token-2022 transfer fees on investor payouts come out of the creator share, then carry_over
+        let (creator_amount, carry_over) = absorb_transfer_fees(creator_amount, carry_over, transfer_fees)?;
+        if transfer_fees > 0 {
+            msg!("Absorbed {} quote tokens of investor transfer fees", transfer_fees);
+        }
 
-    let vault_seed = ctx.accounts.distribution_progress.vault_seed;
-    let seeds = &[QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes(), &[ctx.bumps.quote_treasury_authority]];
-    let signer_seeds = &[&seeds[..]];
+        // A creator share below the sweep threshold waits in carry_over instead of becoming a dust transfer
+        let (creator_amount, carry_over) = if is_last_page {
+            defer_dust_creator_amount(creator_amount, carry_over, creator_sweep_threshold)?
+        } else {
+            (creator_amount, carry_over)
+        };
 
-    token::transfer(transfer_ctx.with_signer(signer_seeds), amount)?;
+        if carry_over > 0 {
+            msg!("Carrying over {} undistributed quote tokens", carry_over);
+        }
 
-    emit!(CreatorPayoutDayClosed {
-        creator_amount: amount,
-        total_investor_distributed: ctx.accounts.distribution_progress.daily_distributed,
-        quote_mint: ctx.accounts.quote_mint.key(),
-        timestamp,
-    });
+        // All token movements happen before the page outcome is committed to progress; any failed
+        // transfer aborts the instruction and Solana reverts every write and transfer made so far
+        if is_last_page {
+            transfer_to_creator(
+                &ctx.accounts.creator_payout_accounts(ctx.bumps.quote_treasury_authority),
+                creator_split_accounts,
+                creator_amount,
+            )?;
+        }
 
-    Ok(())
-}
+        // For wrapped SOL, SOL sent straight to the treasury only becomes spendable once synced
+        sync_native_quote_account(
+            &ctx.accounts.quote_mint.key(),
+            ctx.accounts.quote_treasury.to_account_info(),
+            ctx.accounts.quote_token_program.to_account_info(),
+        )?;
 
-/// @notice Validate that the DAMM V2 pool is configured for quote-only fee collection
-/// @dev Critical security function ensuring honorary position only accrues quote token fees
-/// @dev MUST fail if quote-only collection cannot be guaranteed per bounty requirements
-/// @dev Validates pool configuration to ensure ONLY quote token fees will be accrued
-/// @param ctx The initialization context containing pool and token accounts
-/// @return Result<()> indicating whether pool passes quote-only validation
-fn validate_quote_only_pool(ctx: &Context<InitializeHonoraryPosition>) -> Result<()> {
-    // CRITICAL: This function implements the hard requirement from the bounty:
-    // "Quote‑only fees: The honorary position must accrue fees exclusively in the quote
-    // mint. If this cannot be guaranteed by pool/config parameters, the module must
-    // detect and fail without accepting base‑denominated fees."
+        let progress = &mut ctx.accounts.distribution_progress;
+        commit_page_progress(
+            progress,
+            &PageOutcome {
+                claimed_quote,
+                investor_distributed: total_distributed,
+                investor_count,
+                creator_amount,
+                carry_over,
+                page_cursor: page_index + 1,
+                day_complete: is_last_page,
+                more_pages_needed,
+                page_ts: current_ts,
+            },
+        )?;
+        if is_last_page {
+            emit_creator_payout_day_closed(
+                progress,
+                ctx.accounts.quote_mint.key(),
+                ctx.accounts.quote_mint.decimals,
+                creator_amount,
+                current_ts,
+                false,
+            );
+            if ctx.accounts.policy_config.emit_memo {
+                log_distribution_memo(
+                    ctx.accounts.memo_program.as_deref(),
+                    &distribution_memo(progress, investor_count, creator_amount),
+                )?;
+            }
+            write_distribution_receipt(
+                ctx.accounts.distribution_receipt.as_deref_mut(),
+                progress,
+                creator_amount,
+                y0_total_actual,
+                current_ts,
+            );
+        }
 
-    // Step 1: Validate token order - quote mint must be token B in DAMM V2
-    // In Meteora DLMM V2, token A is typically the base token, token B is the quote token
-    let pool_account_info = ctx.accounts.pool.to_account_info();
-    let pool_data = pool_account_info.data.borrow();
+        set_distribution_result(&DistributionResult {
+            claimed_quote,
+            investor_total_distributed: total_distributed,
+            creator_amount,
+            investor_count,
+            page_index,
+            day_complete: is_last_page,
+            investors_processed: investors_processed as u32,
+            more_pages_needed,
+        })?;
 
-    // Parse pool data to extract token mints (simplified parsing)
-    // In real DAMM V2, this would require proper pool state deserialization
-    if pool_data.len() < 64 {
-        return Err(FeeRoutingError::InvalidQuoteMint.into());
+        Ok(())
     }
 
-    // Extract token A and token B pubkeys from pool state
-    // This is a simplified approach - in production, use proper DAMM V2 pool deserialization
-    let token_a_mint_bytes = &pool_data[8..40]; // Offset after discriminator
-    let token_b_mint_bytes = &pool_data[40..72]; // Next 32 bytes
-
-    let pool_token_a = Pubkey::try_from(token_a_mint_bytes).map_err(|_| FeeRoutingError::InvalidQuoteMint)?;
-    let pool_token_b = Pubkey::try_from(token_b_mint_bytes).map_err(|_| FeeRoutingError::InvalidQuoteMint)?;
+    /// @notice Preview a fee distribution without moving any tokens
+    /// @dev Read-only counterpart of distribute_fees for crank operators and dashboards
+    /// @dev Sizes and splits the pool with the crank's own helpers and the vault's policy, and emits a
+    /// @dev DistributionPreview event; a day in progress is previewed at its next page with the day's snapshot,
+    /// @dev otherwise page 0 of a new day is previewed
+    /// @param ctx The account context with the policy and the optional progress, Bonfida registry and cap ledger
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param claimable_quote Quote fees expected to be claimed from the position, only counted on page 0
+    /// @param investor_weights_bps Optional weight per investor pair, empty means 10000 for everyone
+    /// @param total_pages Number of pages of a new day, as page 0 of the crank would pass it
+    /// @param day_total_locked Locked amount of all of a new day's streams, as page 0 of the crank would pass it
+    /// @return Result<()> indicating success or failure of the preview
+    #[allow(clippy::too_many_arguments)]
+    pub fn preview_distribution<'info>(
+        ctx: Context<'_, '_, '_, 'info, PreviewDistribution<'info>>,
+        vault_seed: u64,
+        claimable_quote: u64,
+        investor_weights_bps: Vec<u16>,
+        total_pages: u32,
+        day_total_locked: u64,
+    ) -> Result<()> {
+        // The crank pays by the policy, so the preview sizes the split from the same values
+        let policy = &ctx.accounts.policy_config;
+        let investor_fee_share_bps = policy.investor_fee_share_bps;
+        validate_fee_share_bps(investor_fee_share_bps)?;
+        let daily_cap_lamports = policy.daily_cap_lamports;
+        let min_payout_lamports = policy.min_payout_lamports;
+        let y0_total = policy.y0_total;
+        validate_investor_weights(&investor_weights_bps, ctx.remaining_accounts.len() / 2)?;
+
+        let current_ts = Clock::get()?.unix_timestamp;
+
+        // Before the first crank there is no progress account, so nothing is carried or distributed yet;
+        // a new day starts with nothing distributed
+        let progress = ctx.accounts.distribution_progress.as_deref().filter(|progress| progress.is_day_in_progress());
+        let carry_over = ctx.accounts.distribution_progress.as_ref().map_or(0, |progress| progress.carry_over);
+        let (page_index, total_pages, daily_distributed) = progress.map_or((0, total_pages, 0), |progress| {
+            (progress.page_cursor, progress.total_pages, progress.daily_distributed)
+        });
+        let is_last_page = page_index + 1 >= total_pages;
 
-    // Step 2: Ensure quote mint is token B (the quote token in the pair)
-    if ctx.accounts.quote_mint.key() != pool_token_b {
-        msg!("Quote mint validation failed:");
-        msg!("  Expected quote mint (token B): {}", pool_token_b);
-        msg!("  Provided quote mint: {}", ctx.accounts.quote_mint.key());
-        return Err(FeeRoutingError::InvalidQuoteMint.into());
-    }
+        let total_fees = calculate_total_fees_for_distribution(page_index, claimable_quote, carry_over)?;
 
-    // Step 3: Validate that token A is the base mint (not the quote)
-    if ctx.accounts.quote_mint.key() == pool_token_a {
-        msg!("Invalid configuration: quote mint cannot be token A (base token)");
-        return Err(FeeRoutingError::BaseFeeDetected.into());
-    }
+        // Query locked amounts with the same pairing checks and participation threshold as the crank
+        let bonfida_registry = ctx.accounts.bonfida_registry.as_deref();
+        let mut total_locked = 0u64;
+        let mut total_weighted_locked = 0u64;
+        let mut total_y0_amount = 0u64;
+        let mut page_locked = Vec::with_capacity(ctx.remaining_accounts.len() / 2);
+        let mut page_weighted_locked = Vec::with_capacity(ctx.remaining_accounts.len() / 2);
 
-    // Step 4: Additional safety checks for pool configuration
-    // Check if pool has any configuration that might cause base token fee accrual
-    if pool_data.len() >= 100 {
-        // In DAMM V2, there might be fee collection modes or tick configurations
-        // that could affect which token fees are collected in
-
-        // This is a simplified check - in production, parse actual pool state
-        // to verify fee collection parameters
-        let fee_config_offset = 80; // Hypothetical offset for fee configuration
-        if pool_data.len() > fee_config_offset + 4 {
-            let fee_mode = u32::from_le_bytes([
-                pool_data[fee_config_offset],
-                pool_data[fee_config_offset + 1],
-                pool_data[fee_config_offset + 2],
-                pool_data[fee_config_offset + 3],
-            ]);
-
-            // Fee mode validation (hypothetical values)
-            // 0 = both tokens, 1 = token A only, 2 = token B only
-            if fee_mode == 1 {
-                msg!("Pool configured for token A (base) fees only - rejecting");
-                return Err(FeeRoutingError::BaseFeeDetected.into());
+        for (investor_index, chunk) in ctx.remaining_accounts.chunks(2).enumerate() {
+            if chunk.len() != 2 {
+                continue; // Skip incomplete pairs
             }
 
-            if fee_mode == 0 {
-                msg!("Pool configured for both token fees - cannot guarantee quote-only");
-                return Err(FeeRoutingError::BaseFeeDetected.into());
+            let locked_position = if is_skipped_stream(&chunk[0], vault_seed, policy.skip_invalid_streams) {
+                None
+            } else {
+                let locked_position = load_locked_position(&chunk[0], vault_seed, current_ts, bonfida_registry)?;
+                validate_investor_ata(&locked_position, &chunk[1])?;
+                Some(locked_position)
+            };
+
+            let locked_amount = locked_position.map_or(0, |position| {
+                participating_locked_amount(position.locked_amount, policy.min_locked_to_participate)
+            });
+            let weighted_locked =
+                calculate_weighted_locked(locked_amount, investor_weight_bps(&investor_weights_bps, investor_index))?;
+            total_locked = total_locked.checked_add(locked_amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            total_weighted_locked =
+                total_weighted_locked.checked_add(weighted_locked).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            page_locked.push(locked_amount);
+            page_weighted_locked.push(weighted_locked);
+
+            if let Some(position) = locked_position {
+                total_y0_amount = total_y0_amount
+                    .checked_add(position.deposited_amount)
+                    .ok_or(FeeRoutingError::ArithmeticOverflow)?;
             }
         }
-    }
 
-    // Step 5: Final validation logging
-    msg!("Quote-only validation passed:");
-    msg!("  Pool token A (base): {}", pool_token_a);
-    msg!("  Pool token B (quote): {}", pool_token_b);
-    msg!("  Validated quote mint: {}", ctx.accounts.quote_mint.key());
-    msg!("  Position will ONLY accrue fees in quote token");
+        // Page 0 takes the day's snapshot like the crank, a day in progress keeps the one it stored
+        let multi_page_day = total_pages > 1;
+        let y0_total_actual =
+            if policy.creator_only { 0 } else { resolve_day_y0_total(total_y0_amount, y0_total, multi_page_day)? };
+        let (day_locked, day_y0_total) = match progress {
+            Some(progress) => (progress.day_total_locked, progress.day_y0_total),
+            None => (snapshot_day_total_locked(day_total_locked, total_locked, multi_page_day)?, y0_total_actual),
+        };
+        let has_day_snapshot = day_locked > 0;
+        let (f_locked_total, y0_total_actual) =
+            if has_day_snapshot { (day_locked, day_y0_total) } else { (total_locked, y0_total_actual) };
+        let day_fee_pool = calculate_day_fee_pool(total_fees, daily_distributed)?;
+
+        let mut f_locked = 0u64;
+        let mut investor_fee_quote = 0u64;
+        let mut investor_payouts = vec![0u64; page_locked.len()];
+        let mut investor_total = 0u64;
+
+        let reserves_day_pool = has_day_snapshot && !policy.creator_only;
+        let (creator_amount, carry_over) = if total_locked == 0 && !reserves_day_pool {
+            // Nothing locked, the crank hands the page's fees to the creator
+            let (creator_amount, carry_over) = calculate_creator_amount_and_carry_over(total_fees, 0, 0)?;
+            defer_dust_creator_amount(creator_amount, carry_over, policy.creator_sweep_threshold)?
+        } else if total_locked == 0 {
+            // Nothing locked on a snapshot day, the crank keeps the day's investor pool like any other page
+            let pool = size_page_investor_pool(
+                policy,
+                day_fee_pool,
+                0,
+                f_locked_total,
+                y0_total_actual,
+                true,
+                daily_distributed,
+            )?;
+            let (creator_amount, carry_over) =
+                calculate_page_settlement(total_fees, pool.day_investor_pool_left, 0, is_last_page)?;
+            if is_last_page {
+                defer_dust_creator_amount(creator_amount, carry_over, policy.creator_sweep_threshold)?
+            } else {
+                (creator_amount, carry_over)
+            }
+        } else {
+            let pool = size_page_investor_pool(
+                policy,
+                day_fee_pool,
+                total_locked,
+                f_locked_total,
+                y0_total_actual,
+                has_day_snapshot,
+                daily_distributed,
+            )?;
+            f_locked = pool.f_locked;
+            investor_fee_quote = pool.investor_fee_quote;
+
+            let investor_shares = allocate_investor_shares(
+                &page_weighted_locked,
+                investor_fee_quote,
+                total_weighted_locked,
+                policy.rounding_mode,
+            )?;
 
-    Ok(())
-}
+            // Streams paying one wallet are paid together, as in the crank
+            let mut wallet_payouts: Vec<WalletPayout> = Vec::with_capacity(page_locked.len());
+            for (investor_index, chunk) in ctx.remaining_accounts.chunks_exact(2).enumerate() {
+                if page_locked[investor_index] == 0 {
+                    continue;
+                }
+                add_wallet_payout(
+                    &mut wallet_payouts,
+                    chunk[1].key(),
+                    investor_index * 2 + 1,
+                    chunk[0].key(),
+                    page_locked[investor_index],
+                    investor_shares[investor_index],
+                )?;
+            }
 
-/// @notice Query locked token amount from a Streamflow contract for pro-rata distribution
-/// @dev Deserializes Streamflow contract data and calculates remaining locked tokens
-/// @dev Uses net_amount_deposited minus amount_withdrawn to get current locked balance
-/// @param stream_account_info The Streamflow contract account containing stream data
-/// @return Result<u64> The amount of tokens currently locked in the stream
-fn get_locked_amount_from_streamflow(stream_account_info: &AccountInfo) -> Result<u64> {
-    // Deserialize the Streamflow contract data
-    let stream_data = &stream_account_info.data.borrow()[..];
+            // What earlier pages of the day paid each wallet counts against the per-investor cap
+            let investor_cap_ledger = ctx.accounts.investor_cap_ledger.as_deref();
+            for wallet in &wallet_payouts {
+                let distributed_today =
+                    daily_distributed.checked_add(investor_total).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+                let paid_today = match (progress, investor_cap_ledger) {
+                    (Some(progress), Some(ledger)) => ledger.paid_on(progress.completed_days, &wallet.investor_ata),
+                    _ => 0,
+                };
+                let (_, payout) = cap_wallet_share(
+                    wallet.share,
+                    distributed_today,
+                    paid_today,
+                    daily_cap_lamports,
+                    policy.per_investor_daily_cap,
+                );
+
+                // Payouts below the threshold are skipped by the crank and carried over
+                if payout < min_payout_lamports {
+                    continue;
+                }
+
+                investor_payouts[wallet.ata_index / 2] = payout;
+                investor_total = investor_total.checked_add(payout).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            }
 
-    // Streamflow contracts don't have discriminators, use try_deserialize
-    use borsh::BorshDeserialize;
-    let mut data_slice = stream_data;
-    let stream_contract =
-        StreamflowContract::deserialize(&mut data_slice).map_err(|_| FeeRoutingError::InvalidStreamflowContract)?;
+            let (creator_amount, carry_over) =
+                calculate_page_settlement(total_fees, pool.day_investor_pool_left, investor_total, is_last_page)?;
+            if is_last_page {
+                defer_dust_creator_amount(creator_amount, carry_over, policy.creator_sweep_threshold)?
+            } else {
+                (creator_amount, carry_over)
+            }
+        };
+
+        emit!(DistributionPreview {
+            total_fees,
+            total_locked,
+            y0_total: y0_total_actual,
+            f_locked_bps: f_locked,
+            eligible_share_bps: eligible_investor_share_bps(investor_fee_share_bps, f_locked) as u64,
+            investor_fee_quote,
+            investor_payouts,
+            creator_amount,
+            carry_over,
+            timestamp: current_ts,
+        });
 
-    // Check if stream is closed
-    if stream_contract.closed {
-        return Ok(0);
+        Ok(())
     }
 
-    // Calculate locked amount = deposited - withdrawn
-    let locked_amount = stream_contract.ix.net_amount_deposited.saturating_sub(stream_contract.amount_withdrawn);
-
-    msg!("Streamflow contract analysis:");
-    msg!("  - Net deposited: {}", stream_contract.ix.net_amount_deposited);
-    msg!("  - Amount withdrawn: {}", stream_contract.amount_withdrawn);
-    msg!("  - Locked amount: {}", locked_amount);
-    msg!("  - Stream closed: {}", stream_contract.closed);
+    /// @notice Emit a snapshot of the distribution progress for front-ends
+    /// @dev Read-only, meant to be simulated so clients don't depend on the account layout
+    /// @param ctx The account context containing the distribution progress
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the read
+    pub fn read_distribution_progress(ctx: Context<ReadDistributionProgress>, vault_seed: u64) -> Result<()> {
+        let progress = &ctx.accounts.distribution_progress;
 
-    Ok(locked_amount)
-}
+        emit!(DistributionProgressSnapshot {
+            vault_seed,
+            last_distribution_ts: progress.last_distribution_ts,
+            daily_distributed: progress.daily_distributed,
+            carry_over: progress.carry_over,
+            page_cursor: progress.page_cursor,
+            total_pages: progress.total_pages,
+            day_complete: progress.day_complete,
+            last_processed_stream: progress.last_processed_stream,
+            last_page_ts: progress.last_page_ts,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-/// @notice Detect if any base token fees were claimed during the fee collection process
-/// @dev This is a critical safety function that enforces the quote-only requirement
-/// @dev Called after each fee claim to ensure no base token fees were accidentally collected
-/// @param base_treasury_before Base token treasury balance before fee claim
-/// @param base_treasury_after Base token treasury balance after fee claim
-/// @param quote_claimed Amount of quote tokens that were claimed
-/// @return Result<()> - fails if any base fees detected
-#[allow(dead_code)]
-fn detect_base_fees(base_treasury_before: u64, base_treasury_after: u64, quote_claimed: u64) -> Result<()> {
-    // Check if base token treasury balance increased
-    if base_treasury_after > base_treasury_before {
-        let base_fees_claimed = base_treasury_after - base_treasury_before;
-        msg!("CRITICAL: Base token fees detected!");
-        msg!("  Base fees claimed: {}", base_fees_claimed);
-        msg!("  Quote fees claimed: {}", quote_claimed);
-        msg!("  This violates the quote-only fee requirement");
-        msg!("  Distribution ABORTED to prevent base token distribution");
-        return Err(FeeRoutingError::BaseFeesClaimedError.into());
+        Ok(())
     }
 
-    // Additional safety check: ensure we actually claimed quote fees
-    if quote_claimed == 0 {
-        msg!("No quote fees claimed - this may indicate a configuration issue");
-        return Err(FeeRoutingError::NoFeesAvailable.into());
-    }
+    /// @notice Emit the fees currently claimable by the honorary position, without claiming them
+    /// @dev Read-only, meant to be simulated so bots can decide whether a crank is worth its fees
+    /// @dev Mirrors the DAMM V2 claim math: settled pending fees plus liquidity times the fee-per-liquidity
+    /// @dev growth since the position's checkpoint
+    /// @param ctx The account context containing the pool, position and position NFT account
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the read
+    pub fn get_pending_fees(ctx: Context<GetPendingFees>, vault_seed: u64) -> Result<()> {
+        let pool = DammV2Pool::try_from_bytes(&ctx.accounts.pool.try_borrow_data()?)?;
+        let position = DammV2Position::try_from_bytes(&ctx.accounts.position.try_borrow_data()?)?;
+
+        if position.pool != ctx.accounts.pool.key() {
+            msg!(
+                "Position {} belongs to pool {}, not {}",
+                ctx.accounts.position.key(),
+                position.pool,
+                ctx.accounts.pool.key()
+            );
+            return Err(FeeRoutingError::InvalidQuoteMint.into());
+        }
 
-    msg!("Base fee detection passed:");
-    msg!("  Base fees claimed: 0 ✓");
-    msg!("  Quote fees claimed: {} ✓", quote_claimed);
-    msg!("  Quote-only requirement satisfied ✓");
+        // Only the vault's honorary position, whose NFT is held by the position owner PDA, can be read
+        let position_nft_account = &ctx.accounts.position_nft_account;
+        if position_nft_account.mint != position.nft_mint || position_nft_account.amount != 1 {
+            msg!("Position NFT {} is not held by the vault's position owner", position.nft_mint);
+            return Err(FeeRoutingError::InvalidPositionOwner.into());
+        }
 
-    Ok(())
-}
+        let pending_quote = position.claimable_fee_b(&pool)?;
+        let pending_base = position.claimable_fee_a(&pool)?;
+        if pending_base > 0 {
+            msg!("WARNING: {} base fees are claimable, a crank would abort", pending_base);
+        }
 
-/// @notice Enhanced validation for quote-only fee collection
-/// @dev Validates that the pool configuration and position setup will only collect quote fees
-/// @dev This function implements multiple layers of validation as required by the bounty
-/// @param pool_account The DAMM V2 pool account info
-/// @param quote_mint_key The expected quote mint pubkey
-/// @param position_info Additional position information for validation
-/// @return Result<()> - fails if quote-only cannot be guaranteed
-#[allow(dead_code)]
-fn validate_quote_only_configuration(
-    pool_account: &AccountInfo,
-    quote_mint_key: &Pubkey,
-    position_info: Option<&AccountInfo>,
-) -> Result<()> {
-    let pool_data = pool_account.data.borrow();
+        emit!(PendingFees {
+            vault_seed,
+            position: ctx.accounts.position.key(),
+            pending_quote,
+            pending_base,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-    // Basic pool data validation
-    if pool_data.len() < 100 {
-        msg!("Pool data too small - invalid DAMM V2 pool");
-        return Err(FeeRoutingError::InvalidQuoteMint.into());
+        Ok(())
     }
 
-    // Extract and validate token mints from pool
-    let token_a_bytes = &pool_data[8..40];
-    let token_b_bytes = &pool_data[40..72];
+    /// @notice Sweep stranded base-token fees from the base treasury to the creator
+    /// @dev Escape hatch for base dust, investors are never paid in the base token
+    /// @dev Only callable by the global state authority
+    /// @param ctx The account context containing the base treasury, creator base ATA, and authority
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the sweep
+    pub fn sweep_base_fees(ctx: Context<SweepBaseFees>, vault_seed: u64) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
 
-    let pool_token_a = Pubkey::try_from(token_a_bytes).map_err(|_| FeeRoutingError::InvalidQuoteMint)?;
-    let pool_token_b = Pubkey::try_from(token_b_bytes).map_err(|_| FeeRoutingError::InvalidQuoteMint)?;
+        let amount = ctx.accounts.base_treasury.amount;
 
-    // Critical validation: quote mint must be token B
-    if quote_mint_key != &pool_token_b {
-        msg!("VALIDATION FAILED: Quote mint is not token B");
-        msg!("  Pool token A: {}", pool_token_a);
-        msg!("  Pool token B: {}", pool_token_b);
-        msg!("  Expected quote: {}", quote_mint_key);
-        return Err(FeeRoutingError::InvalidQuoteMint.into());
-    }
+        if amount > 0 {
+            let vault_seed_bytes = vault_seed.to_le_bytes();
+            let seeds = &[QUOTE_TREASURY_SEED, &vault_seed_bytes, &[ctx.bumps.quote_treasury_authority]];
+            let signer_seeds = &[&seeds[..]];
 
-    // Ensure quote mint is not token A (double check)
-    if quote_mint_key == &pool_token_a {
-        msg!("CRITICAL: Quote mint cannot be token A (base token)");
-        return Err(FeeRoutingError::BaseFeeDetected.into());
-    }
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.base_treasury.to_account_info(),
+                    to: ctx.accounts.creator_base_ata.to_account_info(),
+                    authority: ctx.accounts.quote_treasury_authority.to_account_info(),
+                },
+            );
 
-    // Additional position-level validation if available
-    if let Some(pos_info) = position_info {
-        let pos_data = pos_info.data.borrow();
-        if pos_data.len() >= 8 {
-            // Validate position is configured correctly for quote-only fees
-            // This would require knowledge of the DAMM V2 position structure
-            msg!("Position validation: length {} bytes", pos_data.len());
+            token::transfer(transfer_ctx.with_signer(signer_seeds), amount)?;
         }
+
+        emit!(BaseFeesSwept {
+            amount,
+            base_mint: ctx.accounts.base_mint.key(),
+            creator_base_ata: ctx.accounts.creator_base_ata.key(),
+            vault_seed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
     }
 
-    msg!("Quote-only configuration validated:");
-    msg!("  Token A (base): {}", pool_token_a);
-    msg!("  Token B (quote): {} ✓", pool_token_b);
-    msg!("  Position will collect ONLY quote token fees ✓");
+    /// @notice Close a stuck distribution day and sweep the treasury to the creator
+    /// @dev Escape hatch for a day that can't finish, e.g. when a stream account can no longer be loaded
+    /// @dev Only callable by the global state authority, and only while a day is in progress
+    /// @dev With a creator split configured the sweep is split like the crank's creator remainder, the recipient ATAs
+    /// @dev are passed as remaining accounts in configuration order
+    /// @param ctx The account context containing the progress, quote treasury, creator ATA and authority
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the close
+    pub fn force_close_day<'info>(
+        ctx: Context<'_, '_, '_, 'info, ForceCloseDay<'info>>,
+        vault_seed: u64,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
 
-    Ok(())
-}
+        if !ctx.accounts.distribution_progress.is_day_in_progress() {
+            msg!("No distribution day is in progress");
+            return Err(FeeRoutingError::NoDistributionInProgress.into());
+        }
 
-/// @notice Account structure for initializing the global program state
-/// @dev Defines all accounts required to set up the global configuration
-/// @dev The global_state account stores the creator's fee destination and is a Program Derived Address
-#[derive(Accounts)]
-pub struct InitializeGlobalState<'info> {
-    #[account(
-        init,
-        payer = payer,
-        space = GlobalState::LEN,
-        seeds = [GLOBAL_STATE_SEED],
-        bump
-    )]
-    pub global_state: Account<'info, GlobalState>,
+        let creator_split_count = ctx.accounts.creator_split.as_ref().map_or(0, |split| split.recipients.len());
+        let (_, creator_split_accounts) = split_remaining_accounts(ctx.remaining_accounts, creator_split_count)?;
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+        let amount = ctx.accounts.quote_treasury.amount;
+        let current_ts = Clock::get()?.unix_timestamp;
 
+        transfer_to_creator(
+            &ctx.accounts.creator_payout_accounts(ctx.bumps.quote_treasury_authority),
+            creator_split_accounts,
+            amount,
+        )?;
+
+        // The remaining pages are abandoned, everything left in the treasury went to the creator
+        let progress = &mut ctx.accounts.distribution_progress;
+        let total_pages = progress.total_pages;
+        commit_page_progress(
+            progress,
+            &PageOutcome {
+                claimed_quote: 0,
+                investor_distributed: 0,
+                investor_count: 0,
+                creator_amount: amount,
+                carry_over: 0,
+                page_cursor: total_pages,
+                day_complete: true,
+                more_pages_needed: false,
+                page_ts: current_ts,
+            },
+        )?;
+        emit_creator_payout_day_closed(
+            progress,
+            ctx.accounts.quote_mint.key(),
+            ctx.accounts.quote_mint.decimals,
+            amount,
+            current_ts,
+            true,
+        );
+
+        msg!("Force-closed distribution day of vault {}, swept {} quote tokens to the creator", vault_seed, amount);
+
+        Ok(())
+    }
+
+    /// @notice Pay a vault's accumulated carry-over to the creator
+    /// @dev For vaults whose distributions have stopped, e.g. while winding down, so leftover dust doesn't sit in the
+    /// @dev treasury for good; only the tracked carry_over moves, untracked treasury balance stays where it is
+    /// @dev Only callable by the global state authority, and refused while a distribution day is in progress
+    /// @dev With a creator split configured the carry-over is split like the crank's creator remainder, the recipient
+    /// @dev ATAs are passed as remaining accounts in configuration order
+    /// @param ctx The account context containing the progress, quote treasury, creator ATA and authority
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @return Result<()> indicating success or failure of the flush
+    pub fn flush_carry_over<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlushCarryOver<'info>>,
+        vault_seed: u64,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        if ctx.accounts.distribution_progress.is_day_in_progress() {
+            msg!("Finish or force-close the current distribution day before flushing the carry-over");
+            return Err(FeeRoutingError::DistributionInProgress.into());
+        }
+
+        let amount = ctx.accounts.distribution_progress.carry_over;
+        if amount == 0 {
+            msg!("No carry-over to flush for vault {}", vault_seed);
+            return Ok(());
+        }
+
+        let creator_split_count = ctx.accounts.creator_split.as_ref().map_or(0, |split| split.recipients.len());
+        let (_, creator_split_accounts) = split_remaining_accounts(ctx.remaining_accounts, creator_split_count)?;
+        transfer_to_creator(
+            &ctx.accounts.creator_payout_accounts(ctx.bumps.quote_treasury_authority),
+            creator_split_accounts,
+            amount,
+        )?;
+
+        let progress = &mut ctx.accounts.distribution_progress;
+        progress.carry_over = 0;
+        progress.lifetime_creator_distributed =
+            progress.lifetime_creator_distributed.checked_add(amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+        emit!(CarryOverFlushed {
+            vault_seed,
+            amount,
+            creator_quote_ata: ctx.accounts.creator_quote_ata.key(),
+            quote_mint: ctx.accounts.quote_mint.key(),
+            lifetime_creator_distributed: progress.lifetime_creator_distributed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Flushed {} quote tokens of carry-over to the creator", amount);
+
+        Ok(())
+    }
+
+    /// @notice Close a vault's old distribution receipts and return their rent to the authority
+    /// @dev Only callable by the global state authority; the receipts are passed as writable remaining accounts
+    /// @dev and the receipts of the last `keep_days` closed days can't be closed
+    /// @param ctx The account context containing the global state, progress and authority
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param keep_days Number of most recent days whose receipts are kept
+    /// @return Result<()> - fails with InvalidDistributionReceipt, VaultSeedMismatch or ReceiptTooRecent on a
+    /// @return receipt that can't be closed
+    pub fn close_old_receipts(ctx: Context<CloseOldReceipts>, vault_seed: u64, keep_days: u32) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        let first_kept_day = ctx.accounts.distribution_progress.completed_days.saturating_sub(keep_days);
+        let authority_info = ctx.accounts.authority.to_account_info();
+        let mut lamports_reclaimed = 0u64;
+        for receipt_info in ctx.remaining_accounts {
+            let receipt = load_distribution_receipt(receipt_info, ctx.program_id)?;
+            require_eq!(receipt.vault_seed, vault_seed, FeeRoutingError::VaultSeedMismatch);
+            if receipt.day >= first_kept_day {
+                msg!("Receipt of day {} is kept, only days before {} can be closed", receipt.day, first_kept_day);
+                return Err(FeeRoutingError::ReceiptTooRecent.into());
+            }
+
+            let lamports = close_program_account(receipt_info, &authority_info)?;
+            lamports_reclaimed = lamports_reclaimed.checked_add(lamports).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+        }
+
+        emit!(DistributionReceiptsClosed {
+            vault_seed,
+            receipts_closed: ctx.remaining_accounts.len() as u32,
+            lamports_reclaimed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Closed {} distribution receipts, reclaimed {} lamports",
+            ctx.remaining_accounts.len(),
+            lamports_reclaimed
+        );
+
+        Ok(())
+    }
+
+    /// @notice Transfer the honorary position NFT out of the position owner PDA to a new owner
+    /// @dev Only callable by the global state authority, e.g. to migrate or unwind a vault
+    /// @dev Refused while a distribution day is in progress or while the position still has claimable fees
+    /// @param ctx The account context containing the position, its NFT accounts and authority
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param new_owner Wallet that will own the position NFT
+    /// @return Result<()> indicating success or failure of the transfer
+    pub fn transfer_position_ownership(
+        ctx: Context<TransferPositionOwnership>,
+        vault_seed: u64,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        if ctx.accounts.distribution_progress.is_day_in_progress() {
+            msg!("Finish or force-close the current distribution day before transferring the position");
+            return Err(FeeRoutingError::DistributionInProgress.into());
+        }
+
+        let pool = DammV2Pool::try_from_bytes(&ctx.accounts.pool.try_borrow_data()?)?;
+        let position = DammV2Position::try_from_bytes(&ctx.accounts.position.try_borrow_data()?)?;
+
+        if position.pool != ctx.accounts.pool.key() {
+            msg!(
+                "Position {} belongs to pool {}, not {}",
+                ctx.accounts.position.key(),
+                position.pool,
+                ctx.accounts.pool.key()
+            );
+            return Err(FeeRoutingError::InvalidQuoteMint.into());
+        }
+
+        let position_nft_account = &ctx.accounts.position_nft_account;
+        if position_nft_account.mint != position.nft_mint || position_nft_account.amount != 1 {
+            msg!("Position NFT {} is not held by the vault's position owner", position.nft_mint);
+            return Err(FeeRoutingError::InvalidPositionOwner.into());
+        }
+
+        // Fees accrued so far belong to the investors and creator, they must be cranked out first
+        let pending_quote = position.claimable_fee_b(&pool)?;
+        let pending_base = position.claimable_fee_a(&pool)?;
+        if pending_quote > 0 || pending_base > 0 {
+            msg!("Position has unclaimed fees: {} quote, {} base", pending_quote, pending_base);
+            return Err(FeeRoutingError::UnclaimedFeesOutstanding.into());
+        }
+
+        let vault_seed_bytes = vault_seed.to_le_bytes();
+        let seeds = &[VAULT_SEED, &vault_seed_bytes, INVESTOR_FEE_POSITION_OWNER_SEED, &[ctx.bumps.position_owner_pda]];
+        let signer_seeds = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.nft_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.position_nft_account.to_account_info(),
+                mint: ctx.accounts.position_nft_mint.to_account_info(),
+                to: ctx.accounts.new_owner_nft_account.to_account_info(),
+                authority: ctx.accounts.position_owner_pda.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx.with_signer(signer_seeds), 1, 0)?;
+
+        // The position left the program, with no day in progress and no fees outstanding
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.open_positions = global_state.open_positions.saturating_sub(1);
+
+        emit!(PositionOwnershipTransferred {
+            vault_seed,
+            position: ctx.accounts.position.key(),
+            position_nft_mint: position.nft_mint,
+            old_owner: ctx.accounts.position_owner_pda.key(),
+            new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Transferred position {} to {}", ctx.accounts.position.key(), new_owner);
+
+        Ok(())
+    }
+
+    /// @notice Decommission the deployment and return the global state rent to the authority
+    /// @dev Only callable by the global state authority, and only once no vault holds a honorary position;
+    /// @dev the distribution progress of every vault is passed as a remaining account and must have its
+    /// @dev day complete and no carry-over, fees left in a treasury could no longer be cranked out
+    /// @dev A deployment migrated from the legacy layout never counted its positions and can't be closed
+    /// @param ctx The account context containing the global state and authority
+    /// @return Result<()> - fails with VaultsStillOpen, DistributionInProgress or UnclaimedFeesOutstanding
+    pub fn close_global_state(ctx: Context<CloseGlobalState>) -> Result<()> {
+        require_authority(&ctx.accounts.global_state, &ctx.accounts.authority.key())?;
+
+        if ctx.accounts.global_state.untracked_positions {
+            msg!("Positions opened before the global state migration aren't counted, the deployment can't be closed");
+            return Err(FeeRoutingError::VaultsStillOpen.into());
+        }
+        let open_positions = ctx.accounts.global_state.open_positions;
+        if open_positions > 0 {
+            msg!("{} vaults still hold a honorary position, transfer them out first", open_positions);
+            return Err(FeeRoutingError::VaultsStillOpen.into());
+        }
+
+        for progress_info in ctx.remaining_accounts {
+            require_vault_settled(progress_info, ctx.program_id)?;
+        }
+
+        msg!("Closed global state, rent returned to {}", ctx.accounts.authority.key());
+
+        Ok(())
+    }
+
+    /// @notice Seed the quote treasury with fees for integration tests
+    /// @dev Only compiled with the `test-helpers` feature, stands in for the claim of a real pool
+    /// @dev The deposit is booked as carry_over, so the next crank distributes it like claimed fees
+    /// @param ctx The account context containing the progress, quote treasury and depositor token account
+    /// @param vault_seed Unique identifier for the vault, used in PDA derivation
+    /// @param amount Quote tokens to move from the depositor into the treasury
+    /// @return Result<()> indicating success or failure of the deposit
+    #[cfg(feature = "test-helpers")]
+    pub fn deposit_test_fees(ctx: Context<DepositTestFees>, _vault_seed: u64, amount: u64) -> Result<()> {
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.quote_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.depositor_token_account.to_account_info(),
+                mint: ctx.accounts.quote_mint.to_account_info(),
+                to: ctx.accounts.quote_treasury.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.quote_mint.decimals)?;
+
+        let progress = &mut ctx.accounts.distribution_progress;
+        progress.carry_over = progress.carry_over.checked_add(amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+        msg!("Deposited {} test fees into the quote treasury", amount);
+
+        Ok(())
+    }
+}
+
+/// @notice Validate and write a vault's distribution policy
+/// @dev Shared by initialize_policy_config and initialize_vault
+/// @param policy_config The policy config account being initialized
+/// @param vault_seed Unique identifier for the vault
+/// @param params The vault's policy values
+/// @param bump Bump seed of the policy config PDA
+/// @return Result<()> - fails on an out-of-range fee share, creator floor, distribution interval or epoch start
+fn apply_policy_config(
+    policy_config: &mut PolicyConfig,
+    vault_seed: u64,
+    params: &PolicyConfigParams,
+    bump: u8,
+) -> Result<()> {
+    let distribution_interval_seconds = params.distribution_interval_seconds.unwrap_or(SECONDS_PER_DAY);
+    validate_policy_values(
+        Some(params.investor_fee_share_bps),
+        Some(params.creator_floor_bps),
+        Some(distribution_interval_seconds),
+        Some(params.epoch_start_ts),
+    )?;
+
+    policy_config.investor_fee_share_bps = params.investor_fee_share_bps;
+    policy_config.daily_cap_lamports = params.daily_cap_lamports;
+    policy_config.min_payout_lamports = params.min_payout_lamports;
+    policy_config.y0_total = params.y0_total;
+    policy_config.vault_seed = vault_seed;
+    policy_config.distribution_interval_seconds = distribution_interval_seconds;
+    policy_config.require_allowlist = params.require_allowlist;
+    policy_config.rounding_mode = params.rounding_mode;
+    policy_config.skip_unpayable_investors = params.skip_unpayable_investors;
+    policy_config.creator_floor_bps = params.creator_floor_bps;
+    policy_config.min_locked_to_participate = params.min_locked_to_participate;
+    policy_config.creator_sweep_threshold = params.creator_sweep_threshold;
+    policy_config.allow_empty_investor_set = params.allow_empty_investor_set;
+    policy_config.require_investor_registry = params.require_investor_registry;
+    policy_config.epoch_start_ts = params.epoch_start_ts;
+    policy_config.skip_invalid_streams = params.skip_invalid_streams;
+    policy_config.creator_only = params.creator_only;
+    policy_config.per_investor_daily_cap = params.per_investor_daily_cap;
+    policy_config.emit_memo = params.emit_memo;
+    policy_config.version = PolicyConfig::VERSION;
+    policy_config.bump = bump;
+
+    Ok(())
+}
+
+/// @notice Validate the range-restricted policy values
+/// @dev Shared by initialize_policy_config, initialize_vault and update_policy_config; None skips a value
+/// @param investor_fee_share_bps Basis points allocated to investors
+/// @param creator_floor_bps Minimum share of the fees the creator keeps
+/// @param distribution_interval_seconds Seconds between distributions
+/// @param epoch_start_ts Start of the distribution schedule
+/// @return Result<()> - fails on an out-of-range fee share, creator floor, distribution interval or epoch start
+fn validate_policy_values(
+    investor_fee_share_bps: Option<u16>,
+    creator_floor_bps: Option<u16>,
+    distribution_interval_seconds: Option<i64>,
+    epoch_start_ts: Option<i64>,
+) -> Result<()> {
+    if let Some(investor_fee_share_bps) = investor_fee_share_bps {
+        validate_fee_share_bps(investor_fee_share_bps)?;
+    }
+    if let Some(creator_floor_bps) = creator_floor_bps {
+        validate_creator_floor_bps(creator_floor_bps)?;
+    }
+    if let Some(distribution_interval_seconds) = distribution_interval_seconds {
+        validate_distribution_interval(distribution_interval_seconds)?;
+    }
+    if let Some(epoch_start_ts) = epoch_start_ts {
+        validate_epoch_start(epoch_start_ts)?;
+    }
+
+    Ok(())
+}
+
+/// @notice Count newly created honorary positions on the global state
+/// @param global_state The global state tracking open positions
+/// @param created Number of positions created
+/// @return Result<()> - fails with ArithmeticOverflow if the count overflows
+fn track_position_opened(global_state: &mut GlobalState, created: u32) -> Result<()> {
+    global_state.open_positions =
+        global_state.open_positions.checked_add(created).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// @notice Ensure the signer is the global state authority
+/// @dev Shared guard of every privileged instruction, backing up the has_one constraint on their accounts
+/// @param global_state The global state holding the authority
+/// @param signer The signer of the privileged instruction
+/// @return Result<()> - fails with Unauthorized for any other signer
+fn require_authority(global_state: &GlobalState, signer: &Pubkey) -> Result<()> {
+    require_keys_eq!(*signer, global_state.authority, FeeRoutingError::Unauthorized);
+
+    Ok(())
+}
+
+/// @notice Ensure the program is not paused
+/// @param global_state The global state holding the pause flag
+/// @return Result<()> - fails with ProgramPaused while the emergency stop is active
+fn require_not_paused(global_state: &GlobalState) -> Result<()> {
+    if global_state.paused {
+        msg!("Program is paused");
+        return Err(FeeRoutingError::ProgramPaused.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Apply a partial policy update, validating every provided value
+/// @param policy_config The vault's policy
+/// @param update The fields to change
+/// @return Result<Vec<String>> - the names of the fields whose value changed
+fn apply_policy_update(policy_config: &mut PolicyConfig, update: &PolicyConfigUpdate) -> Result<Vec<String>> {
+    validate_policy_values(
+        update.investor_fee_share_bps,
+        update.creator_floor_bps,
+        update.distribution_interval_seconds,
+        update.epoch_start_ts,
+    )?;
+
+    let mut changed_fields = Vec::new();
+    let changed = &mut changed_fields;
+    update_policy_field(
+        &mut policy_config.investor_fee_share_bps,
+        update.investor_fee_share_bps,
+        "investor_fee_share_bps",
+        changed,
+    );
+    update_policy_field(
+        &mut policy_config.daily_cap_lamports,
+        update.daily_cap_lamports,
+        "daily_cap_lamports",
+        changed,
+    );
+    update_policy_field(
+        &mut policy_config.min_payout_lamports,
+        update.min_payout_lamports,
+        "min_payout_lamports",
+        changed,
+    );
+    update_policy_field(&mut policy_config.y0_total, update.y0_total, "y0_total", changed);
+    update_policy_field(
+        &mut policy_config.distribution_interval_seconds,
+        update.distribution_interval_seconds,
+        "distribution_interval_seconds",
+        changed,
+    );
+    update_policy_field(&mut policy_config.require_allowlist, update.require_allowlist, "require_allowlist", changed);
+    update_policy_field(&mut policy_config.rounding_mode, update.rounding_mode, "rounding_mode", changed);
+    update_policy_field(
+        &mut policy_config.skip_unpayable_investors,
+        update.skip_unpayable_investors,
+        "skip_unpayable_investors",
+        changed,
+    );
+    update_policy_field(&mut policy_config.creator_floor_bps, update.creator_floor_bps, "creator_floor_bps", changed);
+    update_policy_field(
+        &mut policy_config.min_locked_to_participate,
+        update.min_locked_to_participate,
+        "min_locked_to_participate",
+        changed,
+    );
+    update_policy_field(
+        &mut policy_config.creator_sweep_threshold,
+        update.creator_sweep_threshold,
+        "creator_sweep_threshold",
+        changed,
+    );
+    update_policy_field(
+        &mut policy_config.allow_empty_investor_set,
+        update.allow_empty_investor_set,
+        "allow_empty_investor_set",
+        changed,
+    );
+    update_policy_field(
+        &mut policy_config.require_investor_registry,
+        update.require_investor_registry,
+        "require_investor_registry",
+        changed,
+    );
+    update_policy_field(&mut policy_config.epoch_start_ts, update.epoch_start_ts, "epoch_start_ts", changed);
+    update_policy_field(
+        &mut policy_config.skip_invalid_streams,
+        update.skip_invalid_streams,
+        "skip_invalid_streams",
+        changed,
+    );
+    update_policy_field(&mut policy_config.creator_only, update.creator_only, "creator_only", changed);
+    update_policy_field(
+        &mut policy_config.per_investor_daily_cap,
+        update.per_investor_daily_cap,
+        "per_investor_daily_cap",
+        changed,
+    );
+    update_policy_field(&mut policy_config.emit_memo, update.emit_memo, "emit_memo", changed);
+
+    Ok(changed_fields)
+}
+
+/// @notice Set a policy field to its new value, recording its name when the value changes
+/// @param field The policy field
+/// @param value The new value, None leaves the field as it is
+/// @param name Name of the field, reported in PolicyConfigUpdated
+/// @param changed_fields Names of the fields changed so far
+fn update_policy_field<T: PartialEq>(field: &mut T, value: Option<T>, name: &str, changed_fields: &mut Vec<String>) {
+    if let Some(value) = value {
+        if *field != value {
+            *field = value;
+            changed_fields.push(name.to_string());
+        }
+    }
+}
+
+/// @notice Whether a vault's distribution day still has pages left
+/// @dev The progress account only exists after the first crank or rent funding, before that no day is open
+/// @param progress_info The vault's distribution progress PDA
+/// @return Result<bool> - true while a day is in progress
+fn is_distribution_day_open(progress_info: &AccountInfo) -> Result<bool> {
+    if progress_info.owner != &crate::ID || progress_info.data_is_empty() {
+        return Ok(false);
+    }
+
+    let progress = DistributionProgress::try_deserialize(&mut &progress_info.try_borrow_data()?[..])?;
+
+    Ok(progress.is_day_in_progress())
+}
+
+/// @notice Ensure a vault has finished its distribution day and has no fees left to distribute
+/// @param progress_info The vault's distribution progress account
+/// @param program_id This program's id
+/// @return Result<()> - fails with InvalidProgressLayout, DistributionInProgress or UnclaimedFeesOutstanding
+fn require_vault_settled(progress_info: &AccountInfo, program_id: &Pubkey) -> Result<()> {
+    if progress_info.owner != program_id {
+        msg!("Distribution progress {} is not owned by this program", progress_info.key());
+        return Err(FeeRoutingError::InvalidProgressLayout.into());
+    }
+    let progress = DistributionProgress::try_deserialize(&mut &progress_info.try_borrow_data()?[..])
+        .map_err(|_| FeeRoutingError::InvalidProgressLayout)?;
+
+    if progress.is_day_in_progress() {
+        msg!("Vault {} is on page {} of an unfinished day", progress.vault_seed, progress.page_cursor);
+        return Err(FeeRoutingError::DistributionInProgress.into());
+    }
+    if progress.carry_over > 0 {
+        msg!(
+            "Vault {} still carries {} of undistributed fees, flush them first",
+            progress.vault_seed,
+            progress.carry_over
+        );
+        return Err(FeeRoutingError::UnclaimedFeesOutstanding.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Validate the configured distribution interval
+/// @param distribution_interval_seconds Seconds between distributions
+/// @return Result<()> - fails with InvalidDistributionInterval if below the one hour minimum
+fn validate_distribution_interval(distribution_interval_seconds: i64) -> Result<()> {
+    if distribution_interval_seconds < MIN_DISTRIBUTION_INTERVAL_SECONDS {
+        msg!(
+            "Distribution interval {}s is below the {}s minimum",
+            distribution_interval_seconds,
+            MIN_DISTRIBUTION_INTERVAL_SECONDS
+        );
+        return Err(FeeRoutingError::InvalidDistributionInterval.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Validate the configured distribution epoch
+/// @param epoch_start_ts Start of the distribution schedule, 0 when periods follow the first crank
+/// @return Result<()> - fails with InvalidDistributionInterval on a negative epoch
+fn validate_epoch_start(epoch_start_ts: i64) -> Result<()> {
+    if epoch_start_ts < 0 {
+        msg!("Distribution epoch {} is before the Unix epoch", epoch_start_ts);
+        return Err(FeeRoutingError::InvalidDistributionInterval.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Check whether a signer is the configured crank authority
+/// @param global_state The global state holding the optional crank authority
+/// @param signer The crank signer
+/// @return bool Whether the signer may bypass the distribution interval
+fn is_crank_authority(global_state: &GlobalState, signer: &Pubkey) -> bool {
+    global_state.crank_authority.as_ref() == Some(signer)
+}
+
+/// @notice Check whether a new distribution period has started
+/// @dev Page 0 opens a new period and is only allowed once the interval has elapsed
+/// @param page_index Page being cranked
+/// @param current_ts Current Unix timestamp
+/// @param last_distribution_ts Start of the previous distribution period
+/// @param distribution_interval_seconds Seconds between distributions from the policy
+/// @return Result<bool> Whether a new period has started - fails with TooEarlyForDistribution on an early page 0
+fn check_distribution_due(
+    page_index: u32,
+    current_ts: i64,
+    last_distribution_ts: i64,
+    distribution_interval_seconds: i64,
+) -> Result<bool> {
+    let next_distribution_ts =
+        last_distribution_ts.checked_add(distribution_interval_seconds).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    let is_new_period = current_ts >= next_distribution_ts;
+
+    if page_index == 0 && !is_new_period {
+        return Err(FeeRoutingError::TooEarlyForDistribution.into());
+    }
+
+    Ok(is_new_period)
+}
+
+/// @notice Snap the start of a new distribution period to the schedule
+/// @dev A late crank opens the period at the latest interval boundary it has passed, not at its own time,
+/// @dev so the cadence doesn't drift; the first period, or one the crank authority opens early, starts now
+/// @param last_distribution_ts Start of the previous distribution period
+/// @param current_ts Current Unix timestamp
+/// @param distribution_interval_seconds Seconds between distributions from the policy
+/// @return Result<i64> The start of the new distribution period
+fn scheduled_distribution_ts(
+    last_distribution_ts: i64,
+    current_ts: i64,
+    distribution_interval_seconds: i64,
+) -> Result<i64> {
+    let next_distribution_ts =
+        last_distribution_ts.checked_add(distribution_interval_seconds).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    if last_distribution_ts == 0 || current_ts < next_distribution_ts {
+        return Ok(current_ts);
+    }
+
+    let elapsed_periods = (current_ts - last_distribution_ts) / distribution_interval_seconds;
+    let scheduled_ts = elapsed_periods
+        .checked_mul(distribution_interval_seconds)
+        .and_then(|elapsed| last_distribution_ts.checked_add(elapsed))
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok(scheduled_ts)
+}
+
+/// @notice Align the start of a distribution period to the policy's epoch
+/// @dev Periods are epoch_start_ts + n * interval, so every eligibility boundary is known in advance
+/// @dev whenever the cranks actually run; an epoch of 0 leaves the period where the schedule put it
+/// @param period_ts Start of the new period from scheduled_distribution_ts
+/// @param epoch_start_ts Start of the distribution schedule from the policy
+/// @param distribution_interval_seconds Seconds between distributions from the policy
+/// @return Result<i64> The interval boundary at or before period_ts - fails with TooEarlyForDistribution before the epoch
+fn align_to_epoch(period_ts: i64, epoch_start_ts: i64, distribution_interval_seconds: i64) -> Result<i64> {
+    if epoch_start_ts == 0 {
+        return Ok(period_ts);
+    }
+
+    if period_ts < epoch_start_ts {
+        msg!("Distribution schedule starts at {}, it is {}", epoch_start_ts, period_ts);
+        return Err(FeeRoutingError::TooEarlyForDistribution.into());
+    }
+
+    let elapsed_periods = (period_ts - epoch_start_ts) / distribution_interval_seconds;
+    let aligned_ts = elapsed_periods
+        .checked_mul(distribution_interval_seconds)
+        .and_then(|elapsed| epoch_start_ts.checked_add(elapsed))
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok(aligned_ts)
+}
+
+/// @notice Ensure page 0 carries investor pairs unless the policy allows an empty investor set
+/// @dev Checked before the claim, so a crank missing its streams fails without touching the fees
+/// @param investor_pairs Number of investor pairs passed to the page
+/// @param allow_empty_investor_set Whether the policy lets the day's fees go to the creator without investors
+/// @return Result<()> - fails with NoInvestorsProvided for an empty set the policy doesn't allow
+fn validate_investor_set(investor_pairs: usize, allow_empty_investor_set: bool) -> Result<()> {
+    if investor_pairs == 0 && !allow_empty_investor_set {
+        msg!("No investor pairs passed to page 0 and the policy does not allow an empty investor set");
+        return Err(FeeRoutingError::NoInvestorsProvided.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Ensure no distribution day is waiting to be resumed before page 0 opens a new one
+/// @dev page_cursor is authoritative once page 0 has run: the claimed fees sit in the treasury as carry_over
+/// @dev and the remaining pages must be cranked from the cursor, however much time has passed
+/// @param progress The distribution progress for the vault
+/// @return Result<()> - fails with DistributionInProgress while a started day is incomplete
+fn require_no_day_in_progress(progress: &DistributionProgress) -> Result<()> {
+    if progress.is_day_in_progress() {
+        msg!("Day in progress at page {} of {}, resume from the cursor", progress.page_cursor, progress.total_pages);
+        return Err(FeeRoutingError::DistributionInProgress.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Validate that a page is the next one expected by the crank
+/// @dev The cursor only ever moves forward, so a page that was already processed can never be replayed
+/// @dev Pages must also be processed in non-decreasing time order relative to the last recorded page
+/// @param progress The distribution progress for the vault
+/// @param page_index The page the caller wants to process
+/// @param total_pages The page count supplied by the caller, fixed for the whole day on page 0
+/// @param current_ts Current Unix timestamp
+/// @return Result<()> - fails with InvalidPageIndex for stale, skipped or out-of-range pages
+fn validate_page_cursor(
+    progress: &DistributionProgress,
+    page_index: u32,
+    total_pages: u32,
+    current_ts: i64,
+) -> Result<()> {
+    if page_index != progress.page_cursor || total_pages != progress.total_pages || page_index >= total_pages {
+        msg!(
+            "Expected page {} of {}, got page {} of {}",
+            progress.page_cursor,
+            progress.total_pages,
+            page_index,
+            total_pages
+        );
+        return Err(FeeRoutingError::InvalidPageIndex.into());
+    }
+
+    if current_ts < progress.last_page_ts {
+        msg!("Page timestamp {} is older than the last processed page {}", current_ts, progress.last_page_ts);
+        return Err(FeeRoutingError::InvalidPageIndex.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Validate that the investor fee share is a valid basis point value
+/// @param investor_fee_share_bps Basis points allocated to investors
+/// @return Result<()> - fails with InvalidFeeShareBps if the value exceeds 10000
+fn validate_fee_share_bps(investor_fee_share_bps: u16) -> Result<()> {
+    if investor_fee_share_bps > 10000 {
+        msg!("Investor fee share {} bps exceeds 10000", investor_fee_share_bps);
+        return Err(FeeRoutingError::InvalidFeeShareBps.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Validate that the creator floor is a valid basis point value
+/// @param creator_floor_bps Minimum share of the fees the creator keeps
+/// @return Result<()> - fails with InvalidFeeShareBps if the value exceeds 10000
+fn validate_creator_floor_bps(creator_floor_bps: u16) -> Result<()> {
+    if creator_floor_bps > 10000 {
+        msg!("Creator floor {} bps exceeds 10000", creator_floor_bps);
+        return Err(FeeRoutingError::InvalidFeeShareBps.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Resolve the Y0 total used for the locked fraction
+/// @dev Prefers the deposits queried from the streams and falls back to the configured y0_total
+/// @param total_y0_amount Sum of net_amount_deposited across the provided streams
+/// @param y0_total Configured total investor allocation at TGE (Y0)
+/// @return Result<u64> The Y0 total - fails with MissingY0Total if both are zero
+fn resolve_y0_total(total_y0_amount: u64, y0_total: u64) -> Result<u64> {
+    let y0_total_actual = if total_y0_amount > 0 { total_y0_amount } else { y0_total };
+
+    if y0_total_actual == 0 {
+        msg!("Y0 total is zero - no streams deposits found and no y0_total configured");
+        return Err(FeeRoutingError::MissingY0Total.into());
+    }
+
+    Ok(y0_total_actual)
+}
+
+/// @notice Validate a crank of a creator-only vault
+/// @dev Creator-only days are a single page 0 without investor accounts, streams passed anyway would be ignored
+/// @param page_index Page being cranked
+/// @param total_pages Number of pages the caller declared for the day
+/// @param investor_pairs Number of (stream, investor ATA) pairs passed
+/// @return Result<()> - fails with InvalidCreatorOnlyPage for any other page shape
+fn validate_creator_only_page(page_index: u32, total_pages: u32, investor_pairs: usize) -> Result<()> {
+    if page_index != 0 || total_pages != 1 || investor_pairs != 0 {
+        msg!(
+            "Creator-only vaults crank page 0 of 1 without investors, got page {} of {} with {} pairs",
+            page_index,
+            total_pages,
+            investor_pairs
+        );
+        return Err(FeeRoutingError::InvalidCreatorOnlyPage.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Resolve the Y0 total of the day a page belongs to
+/// @dev A single-page day sees every stream and resolves Y0 as before; on a multi-page day the configured
+/// @dev y0_total covers all pages, the page's own deposits are only used when they exceed it
+/// @param total_y0_amount Sum of net_amount_deposited across the page's streams
+/// @param y0_total Configured total investor allocation at TGE (Y0)
+/// @param multi_page_day Whether the day is split across more than one page
+/// @return Result<u64> The Y0 total - fails with MissingY0Total if both are zero
+fn resolve_day_y0_total(total_y0_amount: u64, y0_total: u64, multi_page_day: bool) -> Result<u64> {
+    if multi_page_day {
+        return resolve_y0_total(std::cmp::max(total_y0_amount, y0_total), 0);
+    }
+
+    resolve_y0_total(total_y0_amount, y0_total)
+}
+
+/// @notice Sum the locked and deposited amounts of the streams a compute-limited page 0 left for the extra page
+/// @dev Only read for the day's snapshot, the streams are validated and paid when the extra page processes them
+/// @param tail_accounts The (stream, investor ATA) pairs after the cut-off
+/// @param vault_seed Unique identifier for the vault
+/// @param now Unix timestamp of the crank
+/// @param bonfida_registry The vault's Bonfida registry, if any
+/// @param skip_invalid_streams Whether unreadable streams count as nothing locked
+/// @param min_locked_to_participate Locked amount below which a stream doesn't take part
+/// @return Result<(u64, u64)> The tail's participating locked amount and its deposited amount
+fn sum_page_tail_locked(
+    tail_accounts: &[AccountInfo],
+    vault_seed: u64,
+    now: i64,
+    bonfida_registry: Option<&BonfidaRegistry>,
+    skip_invalid_streams: bool,
+    min_locked_to_participate: u64,
+) -> Result<(u64, u64)> {
+    let mut tail_locked = 0u64;
+    let mut tail_y0_amount = 0u64;
+    for chunk in tail_accounts.chunks_exact(2) {
+        if is_skipped_stream(&chunk[0], vault_seed, skip_invalid_streams) {
+            continue;
+        }
+
+        let position = load_locked_position(&chunk[0], vault_seed, now, bonfida_registry)?;
+        tail_locked = tail_locked
+            .checked_add(participating_locked_amount(position.locked_amount, min_locked_to_participate))
+            .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+        tail_y0_amount =
+            tail_y0_amount.checked_add(position.deposited_amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    }
+
+    Ok((tail_locked, tail_y0_amount))
+}
+
+/// @notice Resolve the day's total locked amount page 0 stores for every page of the day
+/// @dev A single-page day sees all its streams, a multi-page day needs the caller's total of every page;
+/// @dev the day's pages may then process at most that total, less once streams unlocked since page 0
+/// @param day_total_locked Locked amount of all the day's streams passed by the caller, 0 if not given
+/// @param page_total_locked Locked amount of page 0's own streams, with those a compute cut-off left over
+/// @param multi_page_day Whether the caller sent the day as more than one page
+/// @return Result<u64> The snapshot - fails with InvalidLockedSnapshot when a multi-page day has none or the total
+/// @return is below page 0's own streams
+fn snapshot_day_total_locked(day_total_locked: u64, page_total_locked: u64, multi_page_day: bool) -> Result<u64> {
+    if day_total_locked == 0 {
+        if multi_page_day {
+            msg!("A multi-page day needs the day_total_locked of all its pages");
+            return Err(FeeRoutingError::InvalidLockedSnapshot.into());
+        }
+        return Ok(page_total_locked);
+    }
+
+    if day_total_locked < page_total_locked {
+        msg!("Day total locked {} is below page 0's own {}", day_total_locked, page_total_locked);
+        return Err(FeeRoutingError::InvalidLockedSnapshot.into());
+    }
+
+    Ok(day_total_locked)
+}
+
+/// @notice Calculate a page's slice of the day's investor pool
+/// @dev page_pool = day_pool * page_locked / day_total_locked, so every investor gets the same share
+/// @dev whichever page their stream is on
+/// @param day_investor_pool Investor pool of the whole day
+/// @param page_total_locked Locked amount of the page's streams
+/// @param day_total_locked Locked amount of all the day's streams
+/// @return Result<u64> The page's investor pool
+fn calculate_page_investor_pool(day_investor_pool: u64, page_total_locked: u64, day_total_locked: u64) -> Result<u64> {
+    if day_total_locked == 0 {
+        return Ok(0);
+    }
+
+    mul_div_floor(day_investor_pool, page_total_locked, day_total_locked)
+}
+
+/// @notice Investor pool of one page, sized from the day's fees and locked amounts
+struct PageInvestorPool {
+    /// Locked fraction of Y0 in basis points
+    f_locked: u64,
+    /// The page's pool before the daily cap
+    uncapped_investor_fee_quote: u64,
+    /// The page's pool after the daily cap, apportioned across its investors
+    investor_fee_quote: u64,
+    /// What earlier pages left of the day's pool, the last page settles the creator against it
+    day_investor_pool_left: u64,
+}
+
+/// @notice Size a page's investor pool with the vault's policy
+/// @dev Shared by distribute_fees and preview_distribution, so a preview reports the pool the crank pays
+/// @param policy The vault's policy: investor share, creator floor and daily cap
+/// @param day_fee_pool The whole day's fees, see calculate_day_fee_pool
+/// @param page_locked Participating locked amount of the page's streams
+/// @param day_locked Locked amount of the day's snapshot, or page_locked without one
+/// @param y0_total Y0 total of the day
+/// @param has_day_snapshot Whether the page gets its streams' slice of a day-wide pool
+/// @param daily_distributed Investor payouts already made on earlier pages of the day
+/// @return Result<PageInvestorPool> The page's pool - fails with MissingY0Total or ArithmeticOverflow
+fn size_page_investor_pool(
+    policy: &PolicyConfig,
+    day_fee_pool: u64,
+    page_locked: u64,
+    day_locked: u64,
+    y0_total: u64,
+    has_day_snapshot: bool,
+    daily_distributed: u64,
+) -> Result<PageInvestorPool> {
+    let f_locked = calculate_f_locked(day_locked, y0_total)?;
+    let investor_fee_quote = calculate_investor_fee_quote(day_fee_pool, policy.investor_fee_share_bps, f_locked)?;
+
+    // Leave the creator at least the policy's floor share of the day's fees
+    let day_sized_investor_fee_quote = apply_creator_floor(investor_fee_quote, day_fee_pool, policy.creator_floor_bps)?;
+
+    // With a day snapshot the page gets its streams' slice of the day's investor pool
+    let investor_fee_quote = if has_day_snapshot {
+        calculate_page_investor_pool(day_sized_investor_fee_quote, page_locked, day_locked)?
+    } else {
+        day_sized_investor_fee_quote
+    };
+
+    // All pages together never pay out more than the day's investor pool at the full investor share
+    let day_investor_pool = apply_creator_floor(
+        calculate_investor_fee_quote(day_fee_pool, policy.investor_fee_share_bps, 10000)?,
+        day_fee_pool,
+        policy.creator_floor_bps,
+    )?;
+    let uncapped_investor_fee_quote =
+        clip_to_day_investor_pool(investor_fee_quote, day_investor_pool, daily_distributed);
+
+    // Apply daily cap to the page's investor pool, counting payouts already made on earlier pages
+    let investor_fee_quote =
+        clip_to_daily_cap(uncapped_investor_fee_quote, daily_distributed, policy.daily_cap_lamports);
+
+    // The last page settles the creator against what earlier pages left of the day's pool, so their investor
+    // dust is carried over too; without a snapshot the page's capped pool is the only day-level pool there is
+    let day_investor_pool_left = if has_day_snapshot {
+        let day_investor_fee_quote = clip_to_daily_cap(
+            std::cmp::min(day_sized_investor_fee_quote, day_investor_pool),
+            0,
+            policy.daily_cap_lamports,
+        );
+        std::cmp::max(investor_fee_quote, day_investor_fee_quote.saturating_sub(daily_distributed))
+    } else {
+        investor_fee_quote
+    };
+
+    Ok(PageInvestorPool { f_locked, uncapped_investor_fee_quote, investor_fee_quote, day_investor_pool_left })
+}
+
+/// @notice Calculate the fees available to a page
+/// @dev Page 0 adds its claim to the carried-over fees, continuation pages only see the carry-over
+/// @param page_index Page being cranked
+/// @param claimed_quote Quote fees claimed on this page
+/// @param carry_over Quote fees carried over from earlier pages or days
+/// @return Result<u64> The fees available to the page - fails with ArithmeticOverflow instead of wrapping
+fn calculate_total_fees_for_distribution(page_index: u32, claimed_quote: u64, carry_over: u64) -> Result<u64> {
+    if page_index > 0 {
+        return Ok(carry_over);
+    }
+
+    let total_fees = claimed_quote.checked_add(carry_over).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok(total_fees)
+}
+
+/// @notice Calculate the day's fee pool a page computes its investor pool from
+/// @dev Earlier pages only pay investors and keep the rest in carry_over, so adding back what they paid
+/// @dev gives the full claimed pool (plus the carry-over it started with) on every page of the day
+/// @param total_fees_for_distribution Fees still available on this page
+/// @param daily_distributed Investor payouts already made on earlier pages of the day
+/// @return Result<u64> The day's fee pool
+fn calculate_day_fee_pool(total_fees_for_distribution: u64, daily_distributed: u64) -> Result<u64> {
+    let day_fee_pool =
+        total_fees_for_distribution.checked_add(daily_distributed).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok(day_fee_pool)
+}
+
+/// @notice Clip a page's investor pool to what is left of the day's investor pool
+/// @dev Keeps the sum of all pages' payouts within the day's pool when the per-page pools overshoot it
+/// @param investor_fee_quote Investor pool of the page
+/// @param day_investor_pool Investor pool of the whole day
+/// @param daily_distributed Investor payouts already made on earlier pages of the day
+/// @return u64 The page's investor pool
+fn clip_to_day_investor_pool(investor_fee_quote: u64, day_investor_pool: u64, daily_distributed: u64) -> u64 {
+    std::cmp::min(investor_fee_quote, day_investor_pool.saturating_sub(daily_distributed))
+}
+
+/// @notice Calculate the locked fraction of Y0 in basis points
+/// @dev f_locked = floor(total_locked * 10000 / y0_total)
+/// @param total_locked Tokens still locked across all investors
+/// @param y0_total Total investor allocation at TGE (Y0)
+/// @return Result<u64> The locked fraction expressed in basis points
+fn calculate_f_locked(total_locked: u64, y0_total: u64) -> Result<u64> {
+    if y0_total == 0 {
+        return Err(FeeRoutingError::MissingY0Total.into());
+    }
+
+    mul_div_floor(total_locked, BPS_DENOMINATOR, y0_total)
+}
+
+/// @notice Share of the fees investors are eligible for, before the creator floor
+/// @dev eligible_investor_share_bps = min(investor_fee_share_bps, f_locked)
+/// @param investor_fee_share_bps Basis points allocated to investors (max 10000)
+/// @param f_locked Locked fraction of Y0 expressed in basis points
+/// @return u16 The eligible investor share in basis points
+fn eligible_investor_share_bps(investor_fee_share_bps: u16, f_locked: u64) -> u16 {
+    std::cmp::min(investor_fee_share_bps as u64, f_locked) as u16
+}
+
+/// @notice Calculate the investor portion of the fees available for distribution
+/// @dev Applies eligible_investor_share_bps = min(investor_fee_share_bps, f_locked) to the fee pool
+/// @param total_fees Quote fees available for distribution (in token's base units)
+/// @param investor_fee_share_bps Basis points allocated to investors (max 10000)
+/// @param f_locked Locked fraction of Y0 expressed in basis points
+/// @return Result<u64> The quote amount to be split pro-rata across investors
+fn calculate_investor_fee_quote(total_fees: u64, investor_fee_share_bps: u16, f_locked: u64) -> Result<u64> {
+    bps_of(total_fees, eligible_investor_share_bps(investor_fee_share_bps, f_locked))
+}
+
+/// @notice Drop a stream's locked amount when it is below the policy's participation threshold
+/// @dev Excluded streams count neither towards total_locked nor the page's weights, so their share of the
+/// @dev fees stays with the creator instead of turning into dust payouts
+/// @param locked_amount Locked amount reported by the stream
+/// @param min_locked_to_participate Minimum locked amount from the policy
+/// @return u64 The locked amount, or 0 if the stream doesn't participate
+fn participating_locked_amount(locked_amount: u64, min_locked_to_participate: u64) -> u64 {
+    if locked_amount < min_locked_to_participate {
+        0
+    } else {
+        locked_amount
+    }
+}
+
+/// @notice Clamp the investor pool so the creator keeps at least its floor share of the fees
+/// @dev The investor maximum is floored, so rounding always favours the creator's floor
+/// @param investor_fee_quote Investor pool before the floor
+/// @param total_fees Quote fees available for distribution
+/// @param creator_floor_bps Minimum share of the fees the creator keeps in basis points
+/// @return Result<u64> The investor pool after the floor
+fn apply_creator_floor(investor_fee_quote: u64, total_fees: u64, creator_floor_bps: u16) -> Result<u64> {
+    let max_investor_share_bps =
+        (BPS_DENOMINATOR as u16).checked_sub(creator_floor_bps).ok_or(FeeRoutingError::InvalidFeeShareBps)?;
+    let max_investor_fee_quote = bps_of(total_fees, max_investor_share_bps)?;
+
+    Ok(std::cmp::min(investor_fee_quote, max_investor_fee_quote))
+}
+
+/// @notice Whether a distribution page claims the position fees
+/// @dev Only page 0 claims, later pages of the day continue from the claimed carry-over
+/// @param page_index Page being cranked
+/// @return bool - true for the claiming page
+fn page_claims_fees(page_index: u32) -> bool {
+    page_index == 0
+}
+
+/// @notice Bound the number of investor pairs a single page processes
+/// @param investor_pairs Number of (stream, ATA) pairs in the remaining accounts
+/// @return Result<()> - fails with TooManyInvestorsInPage above MAX_INVESTORS_PER_PAGE
+fn validate_investor_page_size(investor_pairs: usize) -> Result<()> {
+    if investor_pairs > MAX_INVESTORS_PER_PAGE {
+        msg!("Page has {} investor pairs, the maximum is {}", investor_pairs, MAX_INVESTORS_PER_PAGE);
+        return Err(FeeRoutingError::TooManyInvestorsInPage.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Validate the optional per-investor weights passed alongside the remaining accounts
+/// @param investor_weights_bps Weight per investor pair, empty means the default weight for everyone
+/// @param investor_pairs Number of (stream, ATA) pairs in the remaining accounts
+/// @return Result<()> - fails with InvalidInvestorData on a length mismatch or a zero weight
+fn validate_investor_weights(investor_weights_bps: &[u16], investor_pairs: usize) -> Result<()> {
+    if investor_weights_bps.is_empty() {
+        return Ok(());
+    }
+
+    if investor_weights_bps.len() != investor_pairs || investor_weights_bps.contains(&0) {
+        msg!("Expected {} non-zero investor weights, got {:?}", investor_pairs, investor_weights_bps);
+        return Err(FeeRoutingError::InvalidInvestorData.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Look up an investor's weight, defaulting to InvestorData::DEFAULT_WEIGHT_BPS
+/// @param investor_weights_bps Weight per investor pair, may be empty
+/// @param investor_index Index of the investor pair in the remaining accounts
+/// @return u16 The investor's weight in basis points
+fn investor_weight_bps(investor_weights_bps: &[u16], investor_index: usize) -> u16 {
+    investor_weights_bps.get(investor_index).copied().unwrap_or(InvestorData::DEFAULT_WEIGHT_BPS)
+}
+
+/// @notice Apply an investor's weight to their locked amount
+/// @dev weighted = floor(investor_locked * weight_bps / 10000), a weight of 10000 leaves the amount unchanged
+/// @param investor_locked Tokens still locked for this investor
+/// @param weight_bps Investor weight in basis points
+/// @return Result<u64> The weighted locked amount
+fn calculate_weighted_locked(investor_locked: u64, weight_bps: u16) -> Result<u64> {
+    bps_of(investor_locked, weight_bps)
+}
+
+/// @notice Calculate a single investor's pro-rata share of the investor pool
+/// @dev payout = floor(investor_locked * investor_fee_quote / total_locked)
+/// @param investor_locked Tokens still locked for this investor
+/// @param investor_fee_quote Investor pool being distributed
+/// @param total_locked Tokens still locked across all investors
+/// @return Result<u64> The investor's payout in quote token base units
+fn calculate_investor_share(investor_locked: u64, investor_fee_quote: u64, total_locked: u64) -> Result<u64> {
+    mul_div_floor(investor_locked, investor_fee_quote, total_locked)
+}
+
+/// @notice Apportion a page's investor pool across its investors
+/// @dev Floor keeps every share at its floor, the remainder stays in the treasury as carry-over
+/// @dev LargestRemainder gives the floor remainder out one lamport each to the largest fractional remainders,
+/// @dev ties going to the earlier investor, so the pool is fully allocated
+/// @param weighted_locked Weighted locked amount of each investor of the page, in page order
+/// @param investor_fee_quote Investor pool for the page
+/// @param total_weighted_locked Sum of the weighted locked amounts
+/// @param rounding_mode The vault's rounding policy
+/// @return Result<Vec<u64>> The share of each investor, in page order
+fn allocate_investor_shares(
+    weighted_locked: &[u64],
+    investor_fee_quote: u64,
+    total_weighted_locked: u64,
+    rounding_mode: RoundingMode,
+) -> Result<Vec<u64>> {
+    let mut shares = weighted_locked
+        .iter()
+        .map(|&weighted| calculate_investor_share(weighted, investor_fee_quote, total_weighted_locked))
+        .collect::<Result<Vec<u64>>>()?;
+
+    if rounding_mode == RoundingMode::Floor || total_weighted_locked == 0 {
+        return Ok(shares);
+    }
+
+    let allocated = shares
+        .iter()
+        .try_fold(0u64, |sum, &share| sum.checked_add(share))
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    let mut leftover = investor_fee_quote.saturating_sub(allocated);
+
+    // Fractional remainders of (weighted * pool) / total, largest first
+    let mut by_remainder: Vec<(u128, usize)> = weighted_locked
+        .iter()
+        .enumerate()
+        .map(|(index, &weighted)| {
+            ((weighted as u128 * investor_fee_quote as u128) % total_weighted_locked as u128, index)
+        })
+        .filter(|(remainder, _)| *remainder > 0)
+        .collect();
+    by_remainder.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    for (_, index) in by_remainder {
+        if leftover == 0 {
+            break;
+        }
+        shares[index] += 1;
+        leftover -= 1;
+    }
+
+    Ok(shares)
+}
+
+/// @notice Check that the daily cap is plausibly scaled to the quote mint's base units
+/// @dev A cap below min_payout_lamports can never pay anyone and is rejected; a cap below one whole quote token
+/// @dev (10^decimals base units) is allowed but logged, as it usually means the cap was given in whole tokens
+/// @param daily_cap_lamports Optional daily cap on investor payouts, in quote base units
+/// @param min_payout_lamports Minimum payout threshold, in quote base units
+/// @param quote_decimals Decimals of the quote mint
+/// @return Result<()> - fails with ImplausibleDailyCap for a cap below the minimum payout
+fn validate_daily_cap_scale(
+    daily_cap_lamports: Option<u64>,
+    min_payout_lamports: u64,
+    quote_decimals: u8,
+) -> Result<()> {
+    let Some(cap) = daily_cap_lamports else {
+        return Ok(());
+    };
+
+    if cap < min_payout_lamports {
+        msg!("Daily cap {} is below the minimum payout {}", cap, min_payout_lamports);
+        return Err(FeeRoutingError::ImplausibleDailyCap.into());
+    }
+
+    let one_token = 10u64.checked_pow(quote_decimals as u32).unwrap_or(u64::MAX);
+    if cap < one_token {
+        msg!("Daily cap {} is below one whole quote token ({} decimals)", cap, quote_decimals);
+    }
+
+    Ok(())
+}
+
+/// @notice Check that the quote mint's decimals can be distributed in u64 base units
+/// @dev Rounding always floors to whole base units and leaves the remainder in the treasury, so distributions
+/// @dev reconcile for any decimals; with 0 decimals the minimum payout and that dust are whole tokens
+/// @param quote_decimals Decimals of the quote mint
+/// @return Result<bool> Whether the decimals are unusual enough to warn about - fails with UnsupportedQuoteDecimals
+/// @return above MAX_QUOTE_DECIMALS
+fn validate_quote_decimals(quote_decimals: u8) -> Result<bool> {
+    if quote_decimals > MAX_QUOTE_DECIMALS {
+        msg!("Quote mint has {} decimals, at most {} are supported", quote_decimals, MAX_QUOTE_DECIMALS);
+        return Err(FeeRoutingError::UnsupportedQuoteDecimals.into());
+    }
+
+    if quote_decimals == 0 {
+        msg!("WARNING: quote mint has 0 decimals, payout thresholds and rounding dust are whole tokens");
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// @notice Clip an investor amount to what is left of the daily cap
+/// @dev The cap is a hard ceiling on cumulative investor payouts across all pages of a day
+/// @param amount Investor amount before applying the cap
+/// @param daily_distributed Investor payouts already made today, including earlier pages
+/// @param daily_cap_lamports Optional daily cap on investor payouts
+/// @return u64 The amount that can still be paid out today
+fn clip_to_daily_cap(amount: u64, daily_distributed: u64, daily_cap_lamports: Option<u64>) -> u64 {
+    match daily_cap_lamports {
+        Some(cap) => std::cmp::min(amount, cap.saturating_sub(daily_distributed)),
+        None => amount,
+    }
+}
+
+/// @notice Clip a single investor's share to what is left of the policy's per-investor daily cap
+/// @dev A wallet paid on several pages of a day is only capped across them when paid_today counts the earlier
+/// @dev pages, which is what the vault's InvestorCapLedger records
+/// @param investor_share Investor's share of the page's pool
+/// @param paid_today Amount already paid to the investor's wallet on earlier pages of the day
+/// @param per_investor_daily_cap Optional ceiling on a single investor's daily payout
+/// @return u64 The share that can be paid out
+fn clip_to_investor_cap(investor_share: u64, paid_today: u64, per_investor_daily_cap: Option<u64>) -> u64 {
+    match per_investor_daily_cap {
+        Some(cap) => std::cmp::min(investor_share, cap.saturating_sub(paid_today)),
+        None => investor_share,
+    }
+}
+
+/// @notice Clip a wallet's share of the page's pool to what the daily and per-investor caps allow
+/// @param share The wallet's share of the page's pool
+/// @param distributed_today Investor payouts made so far today, including this page's
+/// @param paid_today Amount already paid to the wallet on earlier pages of the day
+/// @param daily_cap_lamports Optional daily cap on investor payouts
+/// @param per_investor_daily_cap Optional ceiling on a single investor's daily payout
+/// @return (u64, u64) The share after the daily cap, and after the per-investor cap as well
+fn cap_wallet_share(
+    share: u64,
+    distributed_today: u64,
+    paid_today: u64,
+    daily_cap_lamports: Option<u64>,
+    per_investor_daily_cap: Option<u64>,
+) -> (u64, u64) {
+    let share = clip_to_daily_cap(share, distributed_today, daily_cap_lamports);
+
+    (share, clip_to_investor_cap(share, paid_today, per_investor_daily_cap))
+}
+
+/// @notice Record a wallet's payout in the vault's per-investor cap ledger
+/// @dev The ledger only holds one day, the first payout of a new day clears the previous day's wallets
+/// @param ledger The vault's per-investor cap ledger
+/// @param vault_seed Vault the ledger belongs to
+/// @param day Day being cranked, the distribution progress's completed_days
+/// @param investor_ata ATA the payout was sent to
+/// @param amount Amount paid
+/// @return Result<()> - fails with InvestorCapLedgerFull once MAX_WALLETS wallets were paid on the day
+fn record_wallet_payout(
+    ledger: &mut InvestorCapLedger,
+    vault_seed: u64,
+    day: u32,
+    investor_ata: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    if ledger.vault_seed != vault_seed || ledger.day != day {
+        ledger.vault_seed = vault_seed;
+        ledger.day = day;
+        ledger.wallets.clear();
+    }
+
+    match ledger.wallets.iter_mut().find(|wallet| wallet.investor_ata == investor_ata) {
+        Some(wallet) => wallet.paid = wallet.paid.checked_add(amount).ok_or(FeeRoutingError::ArithmeticOverflow)?,
+        None => {
+            require!(ledger.wallets.len() < InvestorCapLedger::MAX_WALLETS, FeeRoutingError::InvestorCapLedgerFull);
+            ledger.wallets.push(WalletDayPayout { investor_ata, paid: amount });
+        }
+    }
+
+    Ok(())
+}
+
+/// @notice Split the undistributed part of the day's fees between the creator and carry-over
+/// @dev Investor dust (payouts skipped below min_payout_lamports and rounding remainders) is carried
+/// @dev to the next day's investor pool instead of being swept to the creator
+/// @param total_fees_for_distribution Fees available for the day, including previous carry-over
+/// @param investor_fee_quote Investor pool for the day after the daily cap
+/// @param total_distributed Amount actually paid out to investors
+/// @return Result<(u64, u64)> The creator amount and the new carry-over
+fn calculate_creator_amount_and_carry_over(
+    total_fees_for_distribution: u64,
+    investor_fee_quote: u64,
+    total_distributed: u64,
+) -> Result<(u64, u64)> {
+    let creator_amount =
+        total_fees_for_distribution.checked_sub(investor_fee_quote).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    let carry_over = investor_fee_quote.checked_sub(total_distributed).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok((creator_amount, carry_over))
+}
+
+/// @notice Settle a distribution page between the creator and carry-over
+/// @dev Before the last page nothing goes to the creator, everything not paid out stays available for later pages
+/// @dev On the last page the creator share is swept and only investor dust is carried to the next day; passing
+/// @dev what earlier pages left of the day's investor pool carries their dust too, not just the last page's
+/// @param total_fees_for_distribution Fees available for this page, including previous carry-over
+/// @param day_investor_pool_left The day's investor pool after the daily cap, less what earlier pages paid out
+/// @param total_distributed Amount actually paid out to investors on this page
+/// @param is_last_page Whether this page closes the day
+/// @return Result<(u64, u64)> The creator amount and the new carry-over
+fn calculate_page_settlement(
+    total_fees_for_distribution: u64,
+    day_investor_pool_left: u64,
+    total_distributed: u64,
+    is_last_page: bool,
+) -> Result<(u64, u64)> {
+    if !is_last_page {
+        let carry_over =
+            total_fees_for_distribution.checked_sub(total_distributed).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+        return Ok((0, carry_over));
+    }
+
+    calculate_creator_amount_and_carry_over(total_fees_for_distribution, day_investor_pool_left, total_distributed)
+}
+
+/// @notice Publish the outcome of a distribution page as transaction return data
+/// @dev Crank bots can read the Borsh-encoded DistributionResult instead of scraping logs
+/// @param result The page outcome to publish
+/// @return Result<()> indicating success or failure of the serialization
+fn set_distribution_result(result: &DistributionResult) -> Result<()> {
+    let mut data = Vec::new();
+    result.serialize(&mut data).map_err(|_| ErrorCode::AccountDidNotSerialize)?;
+    anchor_lang::solana_program::program::set_return_data(&data);
+
+    Ok(())
+}
+
+/// @notice Claim the fees of the honorary position and of any additional positions into the treasuries
+/// @dev Positions are claimed in sequence into the same treasuries, so their quote fees feed one distribution
+/// @param accounts The distribution accounts containing the honorary position, pool and treasuries
+/// @param additional_positions Account groups of the additional positions, ADDITIONAL_POSITION_ACCOUNTS each
+/// @param position_owner_bump Bump seed of the position owner PDA
+/// @param timestamp Current Unix timestamp for event logging
+/// @return Result<u64> - the total quote amount claimed across all positions
+fn claim_position_fees<'info>(
+    accounts: &mut DistributeFees<'info>,
+    additional_positions: &[AccountInfo<'info>],
+    position_owner_bump: u8,
+    timestamp: i64,
+) -> Result<u64> {
+    let honorary_position = [
+        accounts.pool.to_account_info(),
+        accounts.position.to_account_info(),
+        accounts.position_nft_account.to_account_info(),
+        accounts.token_a_vault.to_account_info(),
+        accounts.token_b_vault.to_account_info(),
+    ];
+    let mut claimed_quote = claim_single_position_fees(accounts, &honorary_position, position_owner_bump, timestamp)?;
+
+    for position_accounts in additional_positions.chunks(ADDITIONAL_POSITION_ACCOUNTS) {
+        // Each extra pool must trade the vault's pair so its fees land in the same treasuries
+        validate_additional_pool(&position_accounts[0], &accounts.token_a_mint.key(), &accounts.quote_mint.key())?;
+        let position_claimed = claim_single_position_fees(accounts, position_accounts, position_owner_bump, timestamp)?;
+        claimed_quote = claimed_quote.checked_add(position_claimed).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    }
+
+    Ok(claimed_quote)
+}
+
+/// @notice Claim one position's fees into the treasuries via the DAMM V2 claim_position_fee CPI
+/// @dev Measures the claim as a treasury delta and fails the crank if any base fees were claimed
+/// @dev Emits QuoteFeesClaimed with both the quote and base amounts
+/// @param accounts The distribution accounts containing the treasuries, mints and position owner
+/// @param position_accounts [pool, position, position_nft_account, token_a_vault, token_b_vault] of the position
+/// @param position_owner_bump Bump seed of the position owner PDA
+/// @param timestamp Current Unix timestamp for event logging
+/// @return Result<u64> - the quote amount claimed
+fn claim_single_position_fees<'info>(
+    accounts: &mut DistributeFees<'info>,
+    position_accounts: &[AccountInfo<'info>],
+    position_owner_bump: u8,
+    timestamp: i64,
+) -> Result<u64> {
+    let pool = &position_accounts[0];
+    let position = &position_accounts[1];
+    let position_nft_account = &position_accounts[2];
+    let token_a_vault = &position_accounts[3];
+    let token_b_vault = &position_accounts[4];
+
+    // Call cp-amm claim_position_fee via CPI
+    let cp_amm_program = accounts.cp_amm_program.to_account_info();
+
+    let vault_seed_bytes = accounts.distribution_progress.vault_seed.to_le_bytes();
+    let seeds = &[VAULT_SEED, &vault_seed_bytes, INVESTOR_FEE_POSITION_OWNER_SEED, &[position_owner_bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    // Snapshot the treasuries and the pool's token A vault so the claim is measured as a delta
+    // and any base-token movement, into the treasury or out of the pool, is detected
+    let base_before = snapshot_base_balances(&accounts.base_treasury.to_account_info(), token_a_vault)?;
+    let quote_treasury_before = accounts.quote_treasury.amount;
+
+    // The treasury authority, position owner and progress are all seeded by vault_seed, the position must
+    // be held by that same owner so a position of another vault can't be claimed into this vault's treasury
+    validate_position_vault(pool, position, position_nft_account, &accounts.position_owner_pda.key())?;
+
+    // Token A fees go to the base treasury and token B fees to the quote treasury, never both to one account
+    let claim_accounts = claim_position_fee_metas(&ClaimPositionFeeKeys {
+        pool_authority: accounts.pool_authority.key(),
+        pool: pool.key(),
+        position: position.key(),
+        base_treasury: accounts.base_treasury.key(),
+        quote_treasury: accounts.quote_treasury.key(),
+        token_a_vault: token_a_vault.key(),
+        token_b_vault: token_b_vault.key(),
+        position_nft_account: position_nft_account.key(),
+        token_a_mint: accounts.token_a_mint.key(),
+        quote_mint: accounts.quote_mint.key(),
+        position_owner: accounts.position_owner_pda.key(),
+        token_program: accounts.token_program.key(),
+        quote_token_program: accounts.quote_token_program.key(),
+        event_authority: accounts.event_authority.key(),
+        cp_amm_program: cp_amm_program.key(),
+    })?;
+
+    // Call claim_position_fee instruction
+    anchor_lang::solana_program::program::invoke_signed(
+        &anchor_lang::solana_program::instruction::Instruction {
+            program_id: cp_amm_program.key(),
+            accounts: claim_accounts,
+            data: [180, 38, 154, 17, 133, 33, 162, 211].to_vec(), // claim_position_fee discriminator
+        },
+        &[
+            accounts.pool_authority.to_account_info(),
+            pool.clone(),
+            position.clone(),
+            accounts.base_treasury.to_account_info(),
+            accounts.quote_treasury.to_account_info(),
+            token_a_vault.clone(),
+            token_b_vault.clone(),
+            position_nft_account.clone(),
+            accounts.token_a_mint.to_account_info(),
+            accounts.quote_mint.to_account_info(),
+            accounts.position_owner_pda.to_account_info(),
+            accounts.token_program.to_account_info(),
+            accounts.quote_token_program.to_account_info(),
+            accounts.event_authority.to_account_info(),
+            cp_amm_program,
+        ],
+        signer_seeds,
+    )?;
+
+    // This enforces the bounty requirement: "Quote‑only enforcement: If any base fees
+    // are observed or a claim returns non‑zero base, the crank must fail deterministically"
+
+    // Refresh cached token balances after the CPI moved tokens
+    accounts.base_treasury.reload()?;
+    accounts.quote_treasury.reload()?;
+
+    // The treasury may still hold carry_over from previous days, so only count the delta
+    let claimed_quote = measure_quote_claim(quote_treasury_before, accounts.quote_treasury.amount)?;
+
+    // Fails with BaseFeesClaimedError if the base treasury grew or base left the pool's token A vault
+    let base_after = snapshot_base_balances(&accounts.base_treasury.to_account_info(), token_a_vault)?;
+    let base_amount_claimed = verify_quote_only_claim(&base_before, &base_after, claimed_quote)?;
+
+    emit!(QuoteFeesClaimed {
+        amount_claimed: claimed_quote,
+        base_amount_claimed,
+        quote_mint: accounts.quote_mint.key(),
+        timestamp,
+        quote_decimals: accounts.quote_mint.decimals,
+    });
+
+    Ok(claimed_quote)
+}
+
+/// @notice Keys of the DAMM V2 claim_position_fee accounts for one position
+struct ClaimPositionFeeKeys {
+    pool_authority: Pubkey,
+    pool: Pubkey,
+    position: Pubkey,
+    /// Destination of token A (base) fees
+    base_treasury: Pubkey,
+    /// Destination of token B (quote) fees
+    quote_treasury: Pubkey,
+    token_a_vault: Pubkey,
+    token_b_vault: Pubkey,
+    position_nft_account: Pubkey,
+    token_a_mint: Pubkey,
+    quote_mint: Pubkey,
+    position_owner: Pubkey,
+    token_program: Pubkey,
+    quote_token_program: Pubkey,
+    event_authority: Pubkey,
+    cp_amm_program: Pubkey,
+}
+
+/// @notice Build the account list of the DAMM V2 claim_position_fee CPI
+/// @dev With token A's mint set to the quote mint, the base treasury ATA resolves to the quote treasury and base
+/// @dev fees would be counted as claimed quote, so the two destinations and their mints must differ
+/// @param keys The claim accounts
+/// @return Result<Vec<AccountMeta>> - the accounts in CPI order, fails with InvalidClaimDestination if the
+/// @return base and quote destinations or mints are the same
+fn claim_position_fee_metas(keys: &ClaimPositionFeeKeys) -> Result<Vec<AccountMeta>> {
+    if keys.base_treasury == keys.quote_treasury || keys.token_a_mint == keys.quote_mint {
+        msg!("Base fees must be claimed into a separate base treasury, got {} for both sides", keys.quote_treasury);
+        return Err(FeeRoutingError::InvalidClaimDestination.into());
+    }
+
+    Ok(vec![
+        AccountMeta::new_readonly(keys.pool_authority, false),
+        AccountMeta::new_readonly(keys.pool, false),
+        AccountMeta::new(keys.position, false),
+        AccountMeta::new(keys.base_treasury, false), // token_a_account (base)
+        AccountMeta::new(keys.quote_treasury, false), // token_b_account (quote)
+        AccountMeta::new(keys.token_a_vault, false),
+        AccountMeta::new(keys.token_b_vault, false),
+        AccountMeta::new(keys.position_nft_account, false),
+        AccountMeta::new_readonly(keys.token_a_mint, false),
+        AccountMeta::new_readonly(keys.quote_mint, false),
+        AccountMeta::new_readonly(keys.position_owner, true),
+        AccountMeta::new_readonly(keys.token_program, false),
+        AccountMeta::new_readonly(keys.quote_token_program, false),
+        AccountMeta::new_readonly(keys.event_authority, false),
+        AccountMeta::new_readonly(keys.cp_amm_program, false),
+    ])
+}
+
+/// @notice Measure a claim as the quote treasury's growth across the claim CPI
+/// @dev A balance left in the treasury before the claim (carry-over, dust) is not part of the claim; the
+/// @dev carry-over is added to the day's pool separately from the progress account
+/// @param quote_treasury_before Quote treasury balance before the claim CPI
+/// @param quote_treasury_after Quote treasury balance after the claim CPI
+/// @return Result<u64> - the claimed quote amount, fails with ClaimReconciliationFailed if the treasury shrank
+fn measure_quote_claim(quote_treasury_before: u64, quote_treasury_after: u64) -> Result<u64> {
+    let Some(claimed_quote) = quote_treasury_after.checked_sub(quote_treasury_before) else {
+        msg!("Quote treasury went from {} to {} during the claim", quote_treasury_before, quote_treasury_after);
+        return Err(FeeRoutingError::ClaimReconciliationFailed.into());
+    };
+
+    Ok(claimed_quote)
+}
+
+/// @notice Validate that a position belongs to the vault being cranked
+/// @dev The position must be a DAMM V2 account of the pool being claimed, and its NFT must sit in a token account
+/// @dev owned by the vault's position owner PDA, which is derived from the same vault_seed as the quote treasury
+/// @dev authority and the distribution progress
+/// @param pool_info The DAMM V2 pool the position is claimed from
+/// @param position_info The DAMM V2 position account
+/// @param position_nft_account_info The token account holding the position NFT
+/// @param position_owner The vault's position owner PDA
+/// @return Result<()> - fails with InvalidPositionOwner for a foreign position or an NFT not held by the vault
+fn validate_position_vault(
+    pool_info: &AccountInfo,
+    position_info: &AccountInfo,
+    position_nft_account_info: &AccountInfo,
+    position_owner: &Pubkey,
+) -> Result<()> {
+    if position_info.owner != &CP_AMM_PROGRAM_ID {
+        msg!("Position {} is not owned by DAMM V2", position_info.key());
+        return Err(FeeRoutingError::InvalidPositionOwner.into());
+    }
+    let position = DammV2Position::try_from_bytes(&position_info.try_borrow_data()?)?;
+    if position.pool != pool_info.key() {
+        msg!("Position {} belongs to pool {}, not {}", position_info.key(), position.pool, pool_info.key());
+        return Err(FeeRoutingError::InvalidPositionOwner.into());
+    }
+
+    if !is_token_program(position_nft_account_info.owner) {
+        msg!("Position NFT account {} is not a token account", position_nft_account_info.key());
+        return Err(FeeRoutingError::InvalidPositionOwner.into());
+    }
+    let nft_data = position_nft_account_info.try_borrow_data()?;
+    let nft_account = token_interface::TokenAccount::try_deserialize(&mut &nft_data[..])
+        .map_err(|_| FeeRoutingError::InvalidPositionOwner)?;
+
+    if nft_account.mint != position.nft_mint || nft_account.amount != 1 || nft_account.owner != *position_owner {
+        msg!(
+            "Position {} NFT is held by {}, not this vault's position owner {}",
+            position_info.key(),
+            nft_account.owner,
+            position_owner
+        );
+        return Err(FeeRoutingError::InvalidPositionOwner.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Resolve the allowlist root the crank must check investor pairs against
+/// @param require_allowlist Whether the policy requires allowlisted investors
+/// @param investor_allowlist The vault's allowlist account, if passed
+/// @param allowlist_proofs Merkle proofs supplied by the caller
+/// @param pair_count Number of investor pairs in the page
+/// @return Result<Option<[u8; 32]>> - the root to check, None when the allowlist is not required
+fn resolve_allowlist_root(
+    require_allowlist: bool,
+    investor_allowlist: Option<&InvestorAllowlist>,
+    allowlist_proofs: &[Vec<[u8; 32]>],
+    pair_count: usize,
+) -> Result<Option<[u8; 32]>> {
+    if !require_allowlist {
+        return Ok(None);
+    }
+
+    let investor_allowlist = investor_allowlist.ok_or_else(|| {
+        msg!("Policy requires an investor allowlist but none was passed");
+        FeeRoutingError::InvestorNotAllowlisted
+    })?;
+
+    if allowlist_proofs.len() != pair_count {
+        msg!("Expected {} allowlist proofs, got {}", pair_count, allowlist_proofs.len());
+        return Err(FeeRoutingError::InvestorNotAllowlisted.into());
+    }
+
+    Ok(Some(investor_allowlist.merkle_root))
+}
+
+/// @notice Compute the allowlist Merkle leaf of an investor pair
+/// @param stream The investor's Streamflow stream
+/// @param investor_ata The investor's quote ATA
+/// @return [u8; 32] - sha256(stream || investor_ata)
+fn allowlist_leaf(stream: &Pubkey, investor_ata: &Pubkey) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[stream.as_ref(), investor_ata.as_ref()]).to_bytes()
+}
+
+/// @notice Verify a Merkle proof for an investor pair
+/// @dev Sibling pairs are hashed in sorted order, so proofs don't need left/right flags
+/// @param root The allowlist Merkle root
+/// @param stream The investor's Streamflow stream
+/// @param investor_ata The investor's quote ATA
+/// @param proof Sibling hashes from the leaf up to the root
+/// @return bool - whether the pair is part of the allowlist
+fn verify_allowlist_proof(root: &[u8; 32], stream: &Pubkey, investor_ata: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(allowlist_leaf(stream, investor_ata), |node, sibling| {
+        let (left, right) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        anchor_lang::solana_program::hash::hashv(&[left.as_ref(), right.as_ref()]).to_bytes()
+    });
+
+    computed == *root
+}
+
+/// @notice Validate the investors of one registry page
+/// @param page_index Distribution page the investors are paid on
+/// @param page_count Number of registered pages
+/// @param investors Investor pairs to register
+/// @return Result<()> - fails with InvalidInvestorData on a bad page, a zero weight or unordered streams
+fn validate_registered_investors(page_index: u32, page_count: u32, investors: &[InvestorData]) -> Result<()> {
+    if page_index >= page_count {
+        msg!("Registry page {} is outside the {} registered pages", page_index, page_count);
+        return Err(FeeRoutingError::InvalidInvestorData.into());
+    }
+
+    validate_investor_page_size(investors.len())?;
+
+    // Same order the crank enforces, so a registered page always passes validate_stream_order
+    let mut last_stream = Pubkey::default();
+    for investor in investors {
+        if investor.weight_bps == 0 || investor.stream_pubkey <= last_stream {
+            msg!("Invalid registered investor {} (weight {})", investor.stream_pubkey, investor.weight_bps);
+            return Err(FeeRoutingError::InvalidInvestorData.into());
+        }
+        last_stream = investor.stream_pubkey;
+    }
+
+    Ok(())
+}
+
+/// @notice Resolve the investor weights of a page, checking its pairs against the registry when required
+/// @param require_investor_registry Whether the policy requires registered investors
+/// @param investor_registry The vault's registry page, if passed
+/// @param page_index Page being cranked
+/// @param total_pages Number of pages in the day
+/// @param investor_pairs (stream, investor ATA) pairs supplied by the caller
+/// @param investor_weights_bps Weights supplied by the caller, must be empty when the registry is used
+/// @return Result<Vec<u16>> - the registered weights, or the caller's weights when the registry is not required
+fn resolve_registered_weights(
+    require_investor_registry: bool,
+    investor_registry: Option<&InvestorRegistry>,
+    page_index: u32,
+    total_pages: u32,
+    investor_pairs: &[(Pubkey, Pubkey)],
+    investor_weights_bps: Vec<u16>,
+) -> Result<Vec<u16>> {
+    if !require_investor_registry {
+        return Ok(investor_weights_bps);
+    }
+
+    if !investor_weights_bps.is_empty() {
+        msg!("Investor weights come from the registry, pass none");
+        return Err(FeeRoutingError::InvalidInvestorData.into());
+    }
+
+    let investor_registry = investor_registry.ok_or_else(|| {
+        msg!("Policy requires the investor registry but no registry page was passed");
+        FeeRoutingError::InvestorNotRegistered
+    })?;
+    if investor_registry.page_index != page_index || investor_registry.page_count != total_pages {
+        msg!(
+            "Registry page {} of {} passed for page {} of {}",
+            investor_registry.page_index,
+            investor_registry.page_count,
+            page_index,
+            total_pages
+        );
+        return Err(FeeRoutingError::InvestorNotRegistered.into());
+    }
+
+    let registered = &investor_registry.investors;
+    let matches = registered.len() == investor_pairs.len()
+        && registered
+            .iter()
+            .zip(investor_pairs)
+            .all(|(investor, (stream, ata))| investor.stream_pubkey == *stream && investor.investor_quote_ata == *ata);
+    if !matches {
+        msg!("Page {} pairs differ from its {} registered investors", page_index, registered.len());
+        return Err(FeeRoutingError::InvestorNotRegistered.into());
+    }
+
+    Ok(registered.iter().map(|investor| investor.weight_bps).collect())
+}
+
+/// @notice Ensure a claim-only call can't change the pool of a day that is being distributed
+/// @param progress The distribution progress for the vault
+/// @return Result<()> - fails with InvalidPageIndex while a multi-page day is in progress
+fn validate_claim_only(progress: &DistributionProgress) -> Result<()> {
+    if progress.is_day_in_progress() {
+        msg!("Cannot claim only while page {} of {} is pending", progress.page_cursor, progress.total_pages);
+        return Err(FeeRoutingError::InvalidPageIndex.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Reset a freshly created distribution progress account
+/// @param progress The distribution progress account to initialize
+/// @param vault_seed Vault seed the progress account belongs to
+/// @param bump Bump seed of the progress PDA
+fn init_distribution_progress(progress: &mut DistributionProgress, vault_seed: u64, bump: u8) {
+    progress.vault_seed = vault_seed;
+    progress.last_distribution_ts = 0;
+    progress.daily_distributed = 0;
+    progress.carry_over = 0;
+    progress.page_cursor = 0;
+    progress.total_pages = 0;
+    progress.day_complete = false;
+    progress.last_processed_stream = Pubkey::default();
+    progress.last_page_ts = 0;
+    progress.lifetime_claimed = 0;
+    progress.lifetime_investor_distributed = 0;
+    progress.lifetime_creator_distributed = 0;
+    progress.completed_days = 0;
+    progress.day_total_locked = 0;
+    progress.day_y0_total = 0;
+    progress.day_locked_processed = 0;
+    progress.day_claimed = 0;
+    progress.day_investor_count = 0;
+    progress.version = DistributionProgress::VERSION;
+    progress.bump = bump;
+}
+
+/// @notice Check a state account's schema version against the one this program writes
+/// @param version Version stored in the account
+/// @param current_version Version written by this program
+/// @return Result<bool> - whether the account needs upgrading, fails with UnsupportedStateVersion for a newer schema
+fn needs_version_upgrade(version: u8, current_version: u8) -> Result<bool> {
+    if version > current_version {
+        msg!("Account schema version {} is newer than the supported version {}", version, current_version);
+        return Err(FeeRoutingError::UnsupportedStateVersion.into());
+    }
+
+    Ok(version < current_version)
+}
+
+/// @notice Convert legacy distribution progress data to the current layout
+/// @dev A legacy day still in progress gets one more page so it can be closed, a closed day keeps its page count;
+/// @dev the stream order guard starts empty and lifetime statistics start from zero
+/// @param data The legacy account data, discriminator included
+/// @return Result<DistributionProgress> - fails with InvalidProgressLayout for any other size or discriminator
+fn migrate_legacy_progress(data: &[u8]) -> Result<DistributionProgress> {
+    if data.len() != LegacyDistributionProgress::LEN || !data.starts_with(DistributionProgress::DISCRIMINATOR) {
+        msg!("Expected a {} byte legacy progress account, got {} bytes", LegacyDistributionProgress::LEN, data.len());
+        return Err(FeeRoutingError::InvalidProgressLayout.into());
+    }
+
+    let legacy = LegacyDistributionProgress::deserialize(&mut &data[DistributionProgress::DISCRIMINATOR.len()..])
+        .map_err(|_| FeeRoutingError::InvalidProgressLayout)?;
+    let total_pages = if legacy.day_complete { legacy.page_cursor } else { legacy.page_cursor.saturating_add(1) };
+
+    Ok(DistributionProgress {
+        last_distribution_ts: legacy.last_distribution_ts,
+        daily_distributed: legacy.daily_distributed,
+        carry_over: legacy.carry_over,
+        page_cursor: legacy.page_cursor,
+        total_pages,
+        day_complete: legacy.day_complete,
+        vault_seed: legacy.vault_seed,
+        last_processed_stream: Pubkey::default(),
+        last_page_ts: legacy.last_distribution_ts,
+        lifetime_claimed: 0,
+        lifetime_investor_distributed: 0,
+        lifetime_creator_distributed: 0,
+        completed_days: 0,
+        day_total_locked: 0,
+        day_y0_total: 0,
+        day_locked_processed: 0,
+        day_claimed: 0,
+        day_investor_count: 0,
+        version: DistributionProgress::VERSION,
+        bump: legacy.bump,
+    })
+}
+
+/// @notice Convert legacy global state data to the current layout
+/// @dev The migrated deployment starts unpaused and without a crank authority; its positions were opened
+/// @dev without being counted, so it starts at 0 open positions with untracked_positions set
+/// @param data The legacy account data, discriminator included
+/// @param authority Signer becoming the global state authority
+/// @param quote_mint Mint of the recorded creator quote ATA
+/// @return Result<GlobalState> - fails with InvalidGlobalStateLayout for any other size or discriminator
+fn migrate_legacy_global_state(data: &[u8], authority: Pubkey, quote_mint: Pubkey) -> Result<GlobalState> {
+    if data.len() != LegacyGlobalState::LEN || !data.starts_with(GlobalState::DISCRIMINATOR) {
+        msg!("Expected a {} byte legacy global state account, got {} bytes", LegacyGlobalState::LEN, data.len());
+        return Err(FeeRoutingError::InvalidGlobalStateLayout.into());
+    }
+
+    let legacy = LegacyGlobalState::deserialize(&mut &data[GlobalState::DISCRIMINATOR.len()..])
+        .map_err(|_| FeeRoutingError::InvalidGlobalStateLayout)?;
+
+    Ok(GlobalState {
+        creator_quote_ata: legacy.creator_quote_ata,
+        authority,
+        paused: false,
+        quote_mint,
+        crank_authority: None,
+        open_positions: 0,
+        untracked_positions: true,
+        version: GlobalState::VERSION,
+        bump: legacy.bump,
+    })
+}
+
+/// @notice An investor wallet's combined payout on a page, summed over every stream paying its ATA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WalletPayout {
+    /// Investor's quote token ATA
+    investor_ata: Pubkey,
+    /// Index of the ATA in the page's investor accounts
+    ata_index: usize,
+    /// First stream on the page paying this ATA
+    stream: Pubkey,
+    /// Number of the page's streams paying this ATA
+    stream_count: u32,
+    /// Locked amount summed over the wallet's streams
+    locked_amount: u64,
+    /// Investor share summed over the wallet's streams
+    share: u64,
+}
+
+/// @notice Add a stream's share to its wallet's payout, opening a new wallet for an ATA not seen on the page
+/// @param wallet_payouts The page's wallets so far, in order of first appearance
+/// @param investor_ata Investor's quote token ATA
+/// @param ata_index Index of the ATA in the page's investor accounts
+/// @param stream Streamflow stream the share was computed from
+/// @param locked_amount Tokens still locked in the stream
+/// @param share The stream's share of the page's investor pool
+/// @return Result<()> indicating success or an arithmetic overflow
+fn add_wallet_payout(
+    wallet_payouts: &mut Vec<WalletPayout>,
+    investor_ata: Pubkey,
+    ata_index: usize,
+    stream: Pubkey,
+    locked_amount: u64,
+    share: u64,
+) -> Result<()> {
+    match wallet_payouts.iter_mut().find(|wallet| wallet.investor_ata == investor_ata) {
+        Some(wallet) => {
+            wallet.stream_count += 1;
+            wallet.locked_amount =
+                wallet.locked_amount.checked_add(locked_amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+            wallet.share = wallet.share.checked_add(share).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+        }
+        None => {
+            wallet_payouts.push(WalletPayout { investor_ata, ata_index, stream, stream_count: 1, locked_amount, share })
+        }
+    }
+
+    Ok(())
+}
+
+/// @notice Outcome of a processed distribution page, committed to progress in one step
+struct PageOutcome {
+    /// Quote fees claimed on this page
+    claimed_quote: u64,
+    /// Investor payouts made on this page
+    investor_distributed: u64,
+    /// Investors paid on this page
+    investor_count: u32,
+    /// Amount paid to the creator on this page
+    creator_amount: u64,
+    /// Quote tokens left in the treasury for later pages or days
+    carry_over: u64,
+    /// The next page index expected by the crank
+    page_cursor: u32,
+    /// Whether this page closed the day
+    day_complete: bool,
+    /// Whether the page was cut short by the compute limit, adding one page to the day
+    more_pages_needed: bool,
+    /// Timestamp at which the page was processed
+    page_ts: i64,
+}
+
+/// @notice Commit the outcome of a distribution page to the progress account
+/// @dev Called only after every token transfer of the page has succeeded, so progress never
+/// @dev describes payouts that did not happen
+/// @dev Also rolls the page into the day's and the vault's lifetime statistics, and grows the day by one page
+/// @dev when the page was cut short by the compute limit
+/// @param progress The distribution progress account to update
+/// @param outcome The page outcome
+/// @return Result<()> - fails with ArithmeticOverflow if a counter would overflow
+fn commit_page_progress(progress: &mut DistributionProgress, outcome: &PageOutcome) -> Result<()> {
+    progress.daily_distributed = progress
+        .daily_distributed
+        .checked_add(outcome.investor_distributed)
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    progress.day_claimed =
+        progress.day_claimed.checked_add(outcome.claimed_quote).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    progress.day_investor_count =
+        progress.day_investor_count.checked_add(outcome.investor_count).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    progress.carry_over = outcome.carry_over;
+    progress.page_cursor = outcome.page_cursor;
+    progress.day_complete = outcome.day_complete;
+    progress.last_page_ts = outcome.page_ts;
+    if outcome.more_pages_needed {
+        progress.total_pages = progress.total_pages.checked_add(1).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    }
+
+    progress.lifetime_claimed =
+        progress.lifetime_claimed.checked_add(outcome.claimed_quote).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    progress.lifetime_investor_distributed = progress
+        .lifetime_investor_distributed
+        .checked_add(outcome.investor_distributed)
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    progress.lifetime_creator_distributed = progress
+        .lifetime_creator_distributed
+        .checked_add(outcome.creator_amount)
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    if outcome.day_complete {
+        progress.completed_days = progress.completed_days.checked_add(1).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    }
+
+    Ok(())
+}
+
+/// @notice Build the event of a newly created honorary position
+/// @dev Carries the NFT mint and account so integrators can cache them for claims straight from the init transaction
+/// @param position The created position
+/// @param position_nft_mint NFT mint of the position
+/// @param position_nft_account Token account holding the position NFT
+/// @param position_owner The position owner PDA
+/// @param vault_seed Unique identifier for the vault
+/// @param quote_mint Quote mint of the vault
+/// @return HonoraryPositionInitialized The event to emit
+fn honorary_position_initialized(
+    position: Pubkey,
+    position_nft_mint: Pubkey,
+    position_nft_account: Pubkey,
+    position_owner: Pubkey,
+    vault_seed: u64,
+    quote_mint: Pubkey,
+) -> HonoraryPositionInitialized {
+    HonoraryPositionInitialized {
+        position,
+        position_owner,
+        vault_seed,
+        lower_tick: 0, // Not used in DAMM V2
+        upper_tick: 0, // Not used in DAMM V2
+        quote_mint,
+        position_nft_mint,
+        position_nft_account,
+    }
+}
+
+/// @notice Emit the day-closing event with the day's and the vault's lifetime totals
+/// @param progress The committed distribution progress
+/// @param quote_mint Quote mint of the vault
+/// @param quote_decimals Decimals of the quote mint
+/// @param creator_amount Amount paid to the creator when closing the day
+/// @param timestamp Current Unix timestamp for event logging
+/// @param forced Whether the authority force-closed the day
+fn emit_creator_payout_day_closed(
+    progress: &DistributionProgress,
+    quote_mint: Pubkey,
+    quote_decimals: u8,
+    creator_amount: u64,
+    timestamp: i64,
+    forced: bool,
+) {
+    emit!(CreatorPayoutDayClosed {
+        creator_amount,
+        total_investor_distributed: progress.daily_distributed,
+        quote_mint,
+        timestamp,
+        lifetime_claimed: progress.lifetime_claimed,
+        lifetime_investor_distributed: progress.lifetime_investor_distributed,
+        lifetime_creator_distributed: progress.lifetime_creator_distributed,
+        completed_days: progress.completed_days,
+        forced,
+        quote_decimals,
+    });
+}
+
+/// @notice Build the summary of a closed day logged through SPL Memo
+/// @dev Progress must already include the closing page, the investor count is the closing page's
+/// @param progress The distribution progress after the closing page was committed
+/// @param investor_count Investors paid on the closing page
+/// @param creator_amount Amount paid to the creator at day close
+/// @return String The memo, e.g. "vault=7 day=3 investors=2 distributed=80000 creator=20000"
+fn distribution_memo(progress: &DistributionProgress, investor_count: u32, creator_amount: u64) -> String {
+    format!(
+        "vault={} day={} investors={} distributed={} creator={}",
+        progress.vault_seed, progress.completed_days, investor_count, progress.daily_distributed, creator_amount
+    )
+}
+
+/// @notice Write the summary of a closed day into its distribution receipt
+/// @dev Progress must already include the closing page; without a day snapshot the locked total is the sum of
+/// @dev the day's pages
+/// @param receipt The day's receipt, None when the crank didn't pass one
+/// @param progress The distribution progress after the closing page was committed
+/// @param creator_amount Amount paid to the creator at day close
+/// @param y0_total Y0 total the day's fees were split with
+/// @param closed_ts Timestamp at which the day closed
+fn write_distribution_receipt(
+    receipt: Option<&mut DistributionReceipt>,
+    progress: &DistributionProgress,
+    creator_amount: u64,
+    y0_total: u64,
+    closed_ts: i64,
+) {
+    let Some(receipt) = receipt else {
+        return;
+    };
+
+    receipt.vault_seed = progress.vault_seed;
+    // The closing page already counted the day as completed
+    receipt.day = progress.completed_days.saturating_sub(1);
+    receipt.claimed = progress.day_claimed;
+    receipt.investor_total = progress.daily_distributed;
+    receipt.creator_amount = creator_amount;
+    receipt.investor_count = progress.day_investor_count;
+    receipt.total_locked =
+        if progress.day_total_locked > 0 { progress.day_total_locked } else { progress.day_locked_processed };
+    receipt.y0 = y0_total;
+    receipt.closed_ts = closed_ts;
+
+    msg!("Recorded distribution receipt for vault {} day {}", receipt.vault_seed, receipt.day);
+}
+
+/// @notice Deserialize a distribution receipt passed as a remaining account
+/// @param receipt_info The receipt account
+/// @param program_id This program's ID, the receipt's owner
+/// @return Result<DistributionReceipt> - fails with InvalidDistributionReceipt for an account of another owner or type
+fn load_distribution_receipt(receipt_info: &AccountInfo, program_id: &Pubkey) -> Result<DistributionReceipt> {
+    if receipt_info.owner != program_id {
+        msg!("Receipt {} is not owned by this program", receipt_info.key());
+        return Err(FeeRoutingError::InvalidDistributionReceipt.into());
+    }
+
+    let receipt = DistributionReceipt::try_deserialize(&mut &receipt_info.try_borrow_data()?[..])
+        .map_err(|_| FeeRoutingError::InvalidDistributionReceipt)?;
+
+    Ok(receipt)
+}
+
+/// @notice Close an account of this program, moving its lamports to a destination
+/// @dev The account is handed back to the system program with no data, so it can't be read as a receipt again
+/// @param account_info The account to close, writable and owned by this program
+/// @param destination_info The account receiving the rent, writable
+/// @return Result<u64> - the lamports moved, fails with ArithmeticOverflow if the destination would overflow
+fn close_program_account(account_info: &AccountInfo, destination_info: &AccountInfo) -> Result<u64> {
+    let lamports = account_info.lamports();
+    let destination_lamports =
+        destination_info.lamports().checked_add(lamports).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    **destination_info.try_borrow_mut_lamports()? = destination_lamports;
+    **account_info.try_borrow_mut_lamports()? = 0;
+
+    account_info.assign(&anchor_lang::system_program::ID);
+    account_info.resize(0)?;
+
+    Ok(lamports)
+}
+
+/// @notice Log a memo through a CPI to the SPL Memo program
+/// @dev The memo names no signers, it only makes the distribution readable in explorers
+/// @param memo_program The SPL Memo program account, None when it was not passed
+/// @param memo The memo text
+/// @return Result<()> - fails with InvalidMemoProgram when the memo program was not passed
+fn log_distribution_memo(memo_program: Option<&AccountInfo>, memo: &str) -> Result<()> {
+    let memo_program = memo_program.ok_or_else(|| {
+        msg!("Policy emits distribution memos but no memo program was passed");
+        FeeRoutingError::InvalidMemoProgram
+    })?;
+
+    anchor_lang::solana_program::program::invoke(
+        &anchor_lang::solana_program::instruction::Instruction {
+            program_id: SPL_MEMO_PROGRAM_ID,
+            accounts: vec![],
+            data: memo.as_bytes().to_vec(),
+        },
+        std::slice::from_ref(memo_program),
+    )?;
+
+    Ok(())
+}
+
+/// @notice Accounts a creator payout moves quote tokens through
+/// @dev Shared by the crank, force_close_day and flush_carry_over so every creator payout honours the creator split
+struct CreatorPayoutAccounts<'a, 'info> {
+    vault_seed: u64,
+    quote_treasury_authority_bump: u8,
+    quote_mint: &'a InterfaceAccount<'info, token_interface::Mint>,
+    quote_treasury: &'a InterfaceAccount<'info, token_interface::TokenAccount>,
+    quote_treasury_authority: &'a UncheckedAccount<'info>,
+    creator_quote_ata: &'a InterfaceAccount<'info, token_interface::TokenAccount>,
+    /// Split of the creator payout, recipient ATAs are passed separately
+    creator_split: Option<&'a Account<'info, CreatorSplit>>,
+    quote_token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'info> DistributeFees<'info> {
+    fn creator_payout_accounts(&self, quote_treasury_authority_bump: u8) -> CreatorPayoutAccounts<'_, 'info> {
+        CreatorPayoutAccounts {
+            vault_seed: self.distribution_progress.vault_seed,
+            quote_treasury_authority_bump,
+            quote_mint: &self.quote_mint,
+            quote_treasury: &self.quote_treasury,
+            quote_treasury_authority: &self.quote_treasury_authority,
+            creator_quote_ata: &self.creator_quote_ata,
+            creator_split: self.creator_split.as_ref(),
+            quote_token_program: &self.quote_token_program,
+        }
+    }
+}
+
+impl<'info> ForceCloseDay<'info> {
+    fn creator_payout_accounts(&self, quote_treasury_authority_bump: u8) -> CreatorPayoutAccounts<'_, 'info> {
+        CreatorPayoutAccounts {
+            vault_seed: self.distribution_progress.vault_seed,
+            quote_treasury_authority_bump,
+            quote_mint: &self.quote_mint,
+            quote_treasury: &self.quote_treasury,
+            quote_treasury_authority: &self.quote_treasury_authority,
+            creator_quote_ata: &self.creator_quote_ata,
+            creator_split: self.creator_split.as_ref(),
+            quote_token_program: &self.quote_token_program,
+        }
+    }
+}
+
+impl<'info> FlushCarryOver<'info> {
+    fn creator_payout_accounts(&self, quote_treasury_authority_bump: u8) -> CreatorPayoutAccounts<'_, 'info> {
+        CreatorPayoutAccounts {
+            vault_seed: self.distribution_progress.vault_seed,
+            quote_treasury_authority_bump,
+            quote_mint: &self.quote_mint,
+            quote_treasury: &self.quote_treasury,
+            quote_treasury_authority: &self.quote_treasury_authority,
+            creator_quote_ata: &self.creator_quote_ata,
+            creator_split: self.creator_split.as_ref(),
+            quote_token_program: &self.quote_token_program,
+        }
+    }
+}
+
+/// @notice Transfer quote token fees to the creator's Associated Token Account
+/// @dev Uses program PDA authority to transfer from quote treasury to creator ATA
+/// @dev With a creator split configured the amount is fanned out across the split recipients instead
+/// @param accounts The treasury, creator and optional creator split accounts of the payout
+/// @param creator_split_accounts Recipient ATAs of the creator split, in configuration order
+/// @param amount The amount of quote tokens to transfer to creator (in token's base units)
+/// @return Result<()> indicating success or failure of the transfer
+fn transfer_to_creator<'info>(
+    accounts: &CreatorPayoutAccounts<'_, 'info>,
+    creator_split_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let vault_seed_bytes = accounts.vault_seed.to_le_bytes();
+    let seeds = &[QUOTE_TREASURY_SEED, &vault_seed_bytes, &[accounts.quote_treasury_authority_bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    // With a Token-2022 transfer fee the creator side bears the fee on its own transfers
+    let transfer_from_treasury = |to: AccountInfo<'info>, transfer_amount: u64| {
+        let transfer_ctx = CpiContext::new(
+            accounts.quote_token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.quote_treasury.to_account_info(),
+                mint: accounts.quote_mint.to_account_info(),
+                to,
+                authority: accounts.quote_treasury_authority.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(
+            transfer_ctx.with_signer(signer_seeds),
+            transfer_amount,
+            accounts.quote_mint.decimals,
+        )
+    };
+
+    if amount > 0 {
+        match accounts.creator_split {
+            Some(creator_split) => {
+                let slices = calculate_creator_split_amounts(amount, &creator_split.recipients)?;
+                for ((recipient, recipient_ata), slice) in
+                    creator_split.recipients.iter().zip(creator_split_accounts).zip(slices)
+                {
+                    if recipient_ata.key() != recipient.ata {
+                        msg!("Creator split recipient {} passed as {}", recipient.ata, recipient_ata.key());
+                        return Err(FeeRoutingError::InvalidCreatorSplit.into());
+                    }
+                    if slice > 0 {
+                        transfer_from_treasury(recipient_ata.clone(), slice)?;
+                    }
+                }
+            }
+            None => transfer_from_treasury(accounts.creator_quote_ata.to_account_info(), amount)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// @notice Validate a creator split configuration
+/// @param recipients Recipient ATAs with their share in basis points
+/// @return Result<()> - fails with InvalidCreatorSplit unless 1-5 distinct recipients with non-zero bps sum to 10000
+fn validate_creator_split(recipients: &[CreatorRecipient]) -> Result<()> {
+    if recipients.is_empty() || recipients.len() > CreatorSplit::MAX_RECIPIENTS {
+        msg!("Creator split has {} recipients, expected 1-{}", recipients.len(), CreatorSplit::MAX_RECIPIENTS);
+        return Err(FeeRoutingError::InvalidCreatorSplit.into());
+    }
+
+    let mut total_bps = 0u32;
+    for (index, recipient) in recipients.iter().enumerate() {
+        if recipient.bps == 0 || recipients[..index].iter().any(|other| other.ata == recipient.ata) {
+            msg!("Creator split recipient {} is zero-weighted or duplicated", recipient.ata);
+            return Err(FeeRoutingError::InvalidCreatorSplit.into());
+        }
+        total_bps += recipient.bps as u32;
+    }
+
+    if total_bps != 10000 {
+        msg!("Creator split sums to {} bps, expected 10000", total_bps);
+        return Err(FeeRoutingError::InvalidCreatorSplit.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Split the creator remainder across the configured recipients
+/// @dev Each slice is floored, rounding dust goes to the first recipient so the slices always sum to amount
+/// @param amount Creator remainder to split
+/// @param recipients Recipient ATAs with their share in basis points
+/// @return Result<Vec<u64>> - the slice for each recipient, in configuration order
+fn calculate_creator_split_amounts(amount: u64, recipients: &[CreatorRecipient]) -> Result<Vec<u64>> {
+    let mut slices = recipients.iter().map(|recipient| bps_of(amount, recipient.bps)).collect::<Result<Vec<u64>>>()?;
+
+    let split_total = slices.iter().sum::<u64>();
+    if let Some(first) = slices.first_mut() {
+        *first = first
+            .checked_add(amount.checked_sub(split_total).ok_or(FeeRoutingError::ArithmeticOverflow)?)
+            .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    }
+
+    Ok(slices)
+}
+
+/// @notice Separate the investor pairs from the creator split recipients in the remaining accounts
+/// @param remaining_accounts All remaining accounts of the instruction
+/// @param creator_split_count Number of creator split recipients appended after the investor pairs
+/// @return Result<(&[T], &[T])> - investor accounts and creator split accounts
+fn split_remaining_accounts<T>(remaining_accounts: &[T], creator_split_count: usize) -> Result<(&[T], &[T])> {
+    let investor_len = remaining_accounts.len().checked_sub(creator_split_count).ok_or_else(|| {
+        msg!("Expected {} creator split recipient accounts", creator_split_count);
+        FeeRoutingError::InvalidCreatorSplit
+    })?;
+
+    Ok(remaining_accounts.split_at(investor_len))
+}
+
+/// @notice Separate the additional position groups leading the remaining accounts
+/// @param remaining_accounts All remaining accounts of the instruction
+/// @param additional_positions Number of additional positions to claim
+/// @return Result<(&[T], &[T])> - additional position accounts and the accounts that follow them
+/// @return fails with InvalidAdditionalPositions if too many positions are requested or accounts are missing
+fn split_additional_positions<T>(remaining_accounts: &[T], additional_positions: u8) -> Result<(&[T], &[T])> {
+    if additional_positions > MAX_ADDITIONAL_POSITIONS {
+        msg!("At most {} additional positions, got {}", MAX_ADDITIONAL_POSITIONS, additional_positions);
+        return Err(FeeRoutingError::InvalidAdditionalPositions.into());
+    }
+
+    let position_len = additional_positions as usize * ADDITIONAL_POSITION_ACCOUNTS;
+    if remaining_accounts.len() < position_len {
+        msg!("Expected {} accounts for {} additional positions", position_len, additional_positions);
+        return Err(FeeRoutingError::InvalidAdditionalPositions.into());
+    }
+
+    Ok(remaining_accounts.split_at(position_len))
+}
+
+/// @notice Validate that an additional position's pool trades the vault's pair with the same token order
+/// @dev The pool must pass the honorary pool's quote-only check too, its base fees would otherwise be claimed
+/// @param pool_info The additional DAMM V2 pool
+/// @param token_a_mint The vault's base mint
+/// @param quote_mint The vault's quote mint
+/// @return Result<()> - fails with InvalidCpAmmProgram for a foreign account, InvalidQuoteMint for another pair
+/// @return and BaseFeeDetected for a pool that can accrue base fees
+fn validate_additional_pool(pool_info: &AccountInfo, token_a_mint: &Pubkey, quote_mint: &Pubkey) -> Result<()> {
+    if pool_info.owner != &CP_AMM_PROGRAM_ID {
+        msg!("Additional pool {} is not owned by DAMM V2", pool_info.key());
+        return Err(FeeRoutingError::InvalidCpAmmProgram.into());
+    }
+
+    let pool_data = pool_info.try_borrow_data()?;
+    let pool = DammV2Pool::try_from_bytes(&pool_data)?;
+    if pool.token_a_mint != *token_a_mint || pool.token_b_mint != *quote_mint {
+        msg!("Additional pool {} trades {} / {}", pool_info.key(), pool.token_a_mint, pool.token_b_mint);
+        return Err(FeeRoutingError::InvalidQuoteMint.into());
+    }
+
+    validate_quote_only_pool_data(&pool_data, quote_mint)
+}
+
+/// @notice Validate that the DAMM V2 pool is configured for quote-only fee collection
+/// @dev Critical security function ensuring honorary position only accrues quote token fees
+/// @dev MUST fail if quote-only collection cannot be guaranteed per bounty requirements
+/// @dev Validates pool configuration to ensure ONLY quote token fees will be accrued
+/// @param ctx The initialization context containing pool and token accounts
+/// @return Result<()> indicating whether pool passes quote-only validation
+fn validate_quote_only_pool(ctx: &Context<InitializeHonoraryPosition>) -> Result<()> {
+    let pool_account_info = ctx.accounts.pool.to_account_info();
+    let pool_data = pool_account_info.data.borrow();
+
+    validate_quote_only_pool_data(&pool_data, &ctx.accounts.quote_mint.key())
+}
+
+/// @notice Validate raw DAMM V2 pool data for quote-only fee collection
+/// @dev Every read is bounds-checked, a short or foreign buffer fails with InvalidQuoteMint instead of panicking
+/// @param pool_data Raw DAMM V2 pool account data
+/// @param quote_mint The quote mint the honorary position must accrue fees in
+/// @return Result<()> indicating whether pool passes quote-only validation
+fn validate_quote_only_pool_data(pool_data: &[u8], quote_mint: &Pubkey) -> Result<()> {
+    // CRITICAL: This function implements the hard requirement from the bounty:
+    // "Quote‑only fees: The honorary position must accrue fees exclusively in the quote
+    // mint. If this cannot be guaranteed by pool/config parameters, the module must
+    // detect and fail without accepting base‑denominated fees."
+
+    // Step 1: Validate token order - quote mint must be token B in DAMM V2
+    // Deserialize the DAMM V2 pool layout to extract token mints
+    let pool = DammV2Pool::try_from_bytes(pool_data)?;
+    let pool_token_a = pool.token_a_mint;
+    let pool_token_b = pool.token_b_mint;
+
+    // Step 2: Ensure quote mint is token B. A quote in token A can't be made quote-only: DAMM V2's collect fee
+    // modes are BothToken and OnlyB, there is no mode charging fees only in token A
+    if *quote_mint != pool_token_b {
+        msg!("Quote mint validation failed:");
+        msg!("  Expected quote mint (token B): {}", pool_token_b);
+        msg!("  Provided quote mint: {}", quote_mint);
+        return Err(FeeRoutingError::InvalidQuoteMint.into());
+    }
+
+    // Step 3: Validate that token A is the base mint (not the quote)
+    if *quote_mint == pool_token_a {
+        msg!("Invalid configuration: quote mint cannot be token A (base token)");
+        return Err(FeeRoutingError::BaseFeeDetected.into());
+    }
+
+    // Step 4: The pool's collect_fee_mode decides which token swap fees are charged in.
+    // Only OnlyB guarantees quote fees; BothToken charges fees in the output token, so every
+    // quote -> base swap would accrue base fees to the position
+    if !pool.collects_fees_only_in_token_b() {
+        msg!("Pool collect_fee_mode {} can accrue token A (base) fees - rejecting", pool.collect_fee_mode);
+        return Err(FeeRoutingError::BaseFeeDetected.into());
+    }
+
+    // Step 5: Final validation logging
+    msg!("Quote-only validation passed:");
+    msg!("  Pool token A (base): {}", pool_token_a);
+    msg!("  Pool token B (quote): {}", pool_token_b);
+    msg!("  Validated quote mint: {}", quote_mint);
+    msg!("  Position will ONLY accrue fees in quote token");
+
+    Ok(())
+}
+
+/// @notice Create a DAMM V2 position owned by a vault's position owner PDA
+/// @dev Accounts follow the DAMM V2 create_position order: owner, nft_mint, nft_account, payer, pool_authority,
+/// @dev pool, position, token_program, system_program, rent, associated_token_program, event_authority, program
+/// @param accounts The create_position accounts, the DAMM V2 program last
+/// @param signer_seeds Seeds of the position owner PDA
+/// @return Result<()> indicating success or failure of the CPI
+fn invoke_create_position<'info>(accounts: [AccountInfo<'info>; 13], signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    // (writable, signer) per account
+    const ACCOUNT_FLAGS: [(bool, bool); 13] = [
+        (false, true),
+        (true, true),
+        (true, false),
+        (true, true),
+        (false, false),
+        (false, false),
+        (true, false),
+        (false, false),
+        (false, false),
+        (false, false),
+        (false, false),
+        (false, false),
+        (false, false),
+    ];
+
+    let account_metas = accounts
+        .iter()
+        .zip(ACCOUNT_FLAGS)
+        .map(|(account, (is_writable, is_signer))| {
+            if is_writable {
+                AccountMeta::new(account.key(), is_signer)
+            } else {
+                AccountMeta::new_readonly(account.key(), is_signer)
+            }
+        })
+        .collect();
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &anchor_lang::solana_program::instruction::Instruction {
+            program_id: accounts[12].key(),
+            accounts: account_metas,
+            data: CREATE_POSITION_DISCRIMINATOR.to_vec(),
+        },
+        &accounts,
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+/// @notice Whether enough compute is left to create one more position in a batch
+/// @param remaining_units Compute units left in the transaction
+/// @return bool - true when at least MIN_COMPUTE_UNITS_PER_POSITION units remain
+fn has_compute_for_position(remaining_units: u64) -> bool {
+    remaining_units >= MIN_COMPUTE_UNITS_PER_POSITION
+}
+
+/// @notice Whether enough compute is left to take one more investor into a distribution page
+/// @dev Every investor counted on the accounting pass is paid on the payout pass, so the estimate reserves
+/// @dev the payouts of all counted investors, the next investor's accounting and payout, and the settlement
+/// @param remaining_units Compute units left in the transaction
+/// @param accounted_investors Investors already counted on the accounting pass
+/// @return bool - true when the next investor fits in the remaining compute
+fn has_compute_for_investor(remaining_units: u64, accounted_investors: u64) -> bool {
+    let required_units = accounted_investors
+        .saturating_add(1)
+        .saturating_mul(INVESTOR_PAYOUT_COMPUTE_UNITS)
+        .saturating_add(INVESTOR_ACCOUNTING_COMPUTE_UNITS)
+        .saturating_add(PAGE_SETTLEMENT_COMPUTE_UNITS);
+    remaining_units >= required_units
+}
+
+/// @notice Validate the remaining accounts describing one vault of a batch
+/// @dev The position owner and treasury authority must be the vault's PDAs and the NFT mint must sign
+/// @param vault_accounts The BATCH_ACCOUNTS_PER_VAULT accounts of the vault
+/// @param vault_seed The vault's seed
+/// @param program_id This program's ID
+/// @return Result<u8> - the position owner PDA bump; fails with BatchAccountsMismatch otherwise
+fn validate_batch_vault_accounts(vault_accounts: &[AccountInfo], vault_seed: u64, program_id: &Pubkey) -> Result<u8> {
+    if vault_accounts.len() != BATCH_ACCOUNTS_PER_VAULT {
+        return Err(FeeRoutingError::BatchAccountsMismatch.into());
+    }
+
+    let vault_seed_bytes = vault_seed.to_le_bytes();
+    let (position_owner_pda, position_owner_bump) =
+        Pubkey::find_program_address(&[VAULT_SEED, &vault_seed_bytes, INVESTOR_FEE_POSITION_OWNER_SEED], program_id);
+    let (quote_treasury_authority, _) =
+        Pubkey::find_program_address(&[QUOTE_TREASURY_SEED, &vault_seed_bytes], program_id);
+
+    if vault_accounts[4].key() != position_owner_pda
+        || vault_accounts[8].key() != quote_treasury_authority
+        || !vault_accounts[2].is_signer
+    {
+        msg!("Batch accounts for vault {} do not match its PDAs or the NFT mint did not sign", vault_seed);
+        return Err(FeeRoutingError::BatchAccountsMismatch.into());
+    }
+
+    Ok(position_owner_bump)
+}
+
+/// @notice Validate that an investor ATA belongs to the recipient of its paired locked position
+/// @dev Accepts the position's recipient_tokens account directly, otherwise the ATA owner must be the recipient
+/// @dev Prevents a crank caller from pairing an investor's locked amount with an arbitrary ATA
+/// @param locked_position The investor's locked position
+/// @param investor_ata_info The quote token account that would receive the payout
+/// @return Result<()> - fails with StreamAtaMismatch if the pair does not match
+fn validate_investor_ata(locked_position: &LockedPosition, investor_ata_info: &AccountInfo) -> Result<()> {
+    if locked_position.recipient_tokens == Some(investor_ata_info.key()) {
+        return Ok(());
+    }
+
+    if !is_token_program(investor_ata_info.owner) {
+        msg!("Investor ATA {} is not an SPL token account", investor_ata_info.key());
+        return Err(FeeRoutingError::StreamAtaMismatch.into());
+    }
+
+    let ata_data = investor_ata_info.data.borrow();
+    let investor_ata = token_interface::TokenAccount::try_deserialize(&mut &ata_data[..])
+        .map_err(|_| FeeRoutingError::StreamAtaMismatch)?;
+
+    if investor_ata.owner != locked_position.recipient {
+        msg!("Stream/ATA mismatch:");
+        msg!("  Stream recipient: {}", locked_position.recipient);
+        msg!("  Investor ATA owner: {}", investor_ata.owner);
+        return Err(FeeRoutingError::StreamAtaMismatch.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Whether a stream is left out of the crank because it can't be deserialized
+/// @dev Only load failures (owner, length, layout, magic or version) are skipped, readable streams with
+/// @dev implausible timestamps still fail the crank
+/// @param stream_account_info The locked account for the investor, of any supported vesting provider
+/// @param vault_seed The vault being distributed
+/// @param skip_invalid_streams Whether the policy skips undeserializable streams
+/// @return bool - true when the stream should be skipped
+fn is_skipped_stream(stream_account_info: &AccountInfo, vault_seed: u64, skip_invalid_streams: bool) -> bool {
+    skip_invalid_streams && !is_loadable_locked_account(stream_account_info, vault_seed)
+}
+
+/// @notice Check whether an investor ATA can receive a quote token transfer
+/// @dev Mirrors the checks the token program makes, so the payout can be skipped instead of reverting the crank
+/// @param investor_ata_info The quote token account that would receive the payout
+/// @param quote_mint The quote mint being distributed
+/// @return Option<PayoutSkipReason> - None when the transfer would succeed
+fn payout_skip_reason(investor_ata_info: &AccountInfo, quote_mint: &Pubkey) -> Option<PayoutSkipReason> {
+    if !is_token_program(investor_ata_info.owner) || investor_ata_info.data_is_empty() {
+        return Some(PayoutSkipReason::AccountClosed);
+    }
+
+    let ata_data = investor_ata_info.data.borrow();
+    let investor_ata = match token_interface::TokenAccount::try_deserialize(&mut &ata_data[..]) {
+        Ok(investor_ata) => investor_ata,
+        Err(_) => return Some(PayoutSkipReason::AccountClosed),
+    };
+
+    if investor_ata.is_frozen() {
+        return Some(PayoutSkipReason::AccountFrozen);
+    }
+
+    if investor_ata.mint != *quote_mint {
+        return Some(PayoutSkipReason::MintMismatch);
+    }
+
+    None
+}
+
+/// @notice Whether an account is owned by the SPL Token or the Token-2022 program
+/// @param owner Owner program of the account
+/// @return bool - true for either token program
+fn is_token_program(owner: &Pubkey) -> bool {
+    owner == &token::ID || owner == &token_2022::ID
+}
+
+/// @notice Whether the quote mint is wrapped SOL, for SPL Token or Token-2022
+/// @param quote_mint The quote mint
+/// @return bool - true for either native mint
+fn is_native_quote_mint(quote_mint: &Pubkey) -> bool {
+    quote_mint == &token::spl_token::native_mint::ID || quote_mint == &token_2022::spl_token_2022::native_mint::ID
+}
+
+/// @notice Sync a wrapped SOL token account so its token amount matches its lamports
+/// @dev No-op for any other quote mint
+/// @param quote_mint The quote mint
+/// @param account The native token account to sync
+/// @param quote_token_program Token program of the quote mint
+/// @return Result<()> indicating success or failure of the sync
+fn sync_native_quote_account<'info>(
+    quote_mint: &Pubkey,
+    account: AccountInfo<'info>,
+    quote_token_program: AccountInfo<'info>,
+) -> Result<()> {
+    if !is_native_quote_mint(quote_mint) {
+        return Ok(());
+    }
+
+    token_interface::sync_native(CpiContext::new(quote_token_program, token_interface::SyncNative { account }))
+}
+
+/// @notice Compute the amount to transfer so the recipient receives `net` after the mint's transfer fee
+/// @dev SPL Token mints and Token-2022 mints without a TransferFeeConfig charge no fee, so gross == net
+/// @param quote_mint_info The quote mint account
+/// @param net Amount the recipient must receive
+/// @param epoch Current epoch, Token-2022 schedules fee changes by epoch
+/// @return Result<u64> - the transfer-fee-inclusive gross amount
+fn gross_amount_for_net(quote_mint_info: &AccountInfo, net: u64, epoch: u64) -> Result<u64> {
+    if quote_mint_info.owner != &token_2022::ID || net == 0 {
+        return Ok(net);
+    }
+
+    let mint_data = quote_mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<Token2022Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(net);
+    };
+
+    let gross = transfer_fee_config
+        .get_epoch_fee(epoch)
+        .calculate_pre_fee_amount(net)
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    Ok(gross)
+}
+
+/// @notice Take the transfer fees paid on investor payouts out of the page settlement
+/// @dev The creator share pays first, whatever it can't cover comes out of carry_over
+/// @param creator_amount Creator amount of the page settlement
+/// @param carry_over Carry-over of the page settlement
+/// @param transfer_fees Transfer fees withheld on the page's investor payouts
+/// @return Result<(u64, u64)> The creator amount and carry-over after the fees
+fn absorb_transfer_fees(creator_amount: u64, carry_over: u64, transfer_fees: u64) -> Result<(u64, u64)> {
+    let from_creator = transfer_fees.min(creator_amount);
+    let from_carry_over = transfer_fees.checked_sub(from_creator).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    let carry_over = carry_over.checked_sub(from_carry_over).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    let creator_amount = creator_amount.checked_sub(from_creator).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok((creator_amount, carry_over))
+}
+
+/// @notice Carry a creator amount below the sweep threshold over to the next day
+/// @dev A zero threshold disables the check, a zero creator amount is left as is
+/// @param creator_amount Creator amount of the day's settlement
+/// @param carry_over Carry-over of the day's settlement
+/// @param creator_sweep_threshold Minimum creator amount worth a transfer
+/// @return Result<(u64, u64)> The creator amount and carry-over after deferring dust
+fn defer_dust_creator_amount(creator_amount: u64, carry_over: u64, creator_sweep_threshold: u64) -> Result<(u64, u64)> {
+    if creator_amount == 0 || creator_amount >= creator_sweep_threshold {
+        return Ok((creator_amount, carry_over));
+    }
+
+    msg!(
+        "Creator amount {} is below the sweep threshold {}, carrying it over",
+        creator_amount,
+        creator_sweep_threshold
+    );
+    let carry_over = carry_over.checked_add(creator_amount).ok_or(FeeRoutingError::ArithmeticOverflow)?;
+    Ok((0, carry_over))
+}
+
+/// @notice Enforce strictly ascending stream pubkeys within and across the pages of a day
+/// @dev The highest processed stream is persisted in DistributionProgress, so replaying a stream on a
+/// @dev later page (or twice within one page) is rejected and cannot be double-paid
+/// @param last_processed_stream Highest stream pubkey processed so far today
+/// @param stream The next stream pubkey to process
+/// @return Result<()> - fails with DuplicateStreamInDistribution if the order is violated
+fn validate_stream_order(last_processed_stream: &Pubkey, stream: &Pubkey) -> Result<()> {
+    if stream <= last_processed_stream {
+        msg!("Stream {} is not after last processed stream {}", stream, last_processed_stream);
+        return Err(FeeRoutingError::DuplicateStreamInDistribution.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Ensure a distribution day has something to distribute
+/// @dev A zero claim is fine as long as carry_over from earlier days is waiting in the treasury
+/// @param claimed_quote Quote fees claimed on this page
+/// @param carry_over Quote fees carried over from earlier pages or days
+/// @return Result<()> - fails with NoFeesAvailable only when both are zero
+fn validate_fees_available(claimed_quote: u64, carry_over: u64) -> Result<()> {
+    if claimed_quote == 0 && carry_over == 0 {
+        msg!("No quote fees claimed and nothing carried over - this may indicate a configuration issue");
+        return Err(FeeRoutingError::NoFeesAvailable.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Detect if any base token fees were claimed during the fee collection process
+/// @dev This is a critical safety function that enforces the quote-only requirement
+/// @dev Called after each fee claim to ensure no base token fees were accidentally collected
+/// @param base_treasury_before Base token treasury balance before fee claim
+/// @param base_treasury_after Base token treasury balance after fee claim
+/// @param quote_claimed Amount of quote tokens that were claimed
+/// @return Result<u64> - the base amount claimed, always 0 on success; fails if any base fees detected
+fn detect_base_fees(base_treasury_before: u64, base_treasury_after: u64, quote_claimed: u64) -> Result<u64> {
+    // Check if base token treasury balance increased
+    let base_fees_claimed = base_treasury_after.saturating_sub(base_treasury_before);
+    if base_fees_claimed > 0 {
+        msg!("CRITICAL: Base token fees detected!");
+        msg!("  Base fees claimed: {}", base_fees_claimed);
+        msg!("  Quote fees claimed: {}", quote_claimed);
+        msg!("  This violates the quote-only fee requirement");
+        msg!("  Distribution ABORTED to prevent base token distribution");
+        return Err(FeeRoutingError::BaseFeesClaimedError.into());
+    }
+
+    msg!("Base fee detection passed:");
+    msg!("  Base fees claimed: 0 ✓");
+    msg!("  Quote fees claimed: {} ✓", quote_claimed);
+    msg!("  Quote-only requirement satisfied ✓");
+
+    Ok(base_fees_claimed)
+}
+
+/// @notice Base token balances on both sides of the claim CPI
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BaseBalances {
+    /// Base treasury balance, where a claim deposits token A fees
+    base_treasury: u64,
+    /// Pool's token A vault balance, where a claim takes token A fees from
+    token_a_vault: u64,
+}
+
+/// @notice Read the amount of an SPL token account without deserializing the whole account
+/// @dev The amount follows the mint and owner in both the Token and Token-2022 layouts
+/// @param token_account The token account to read
+/// @return Result<u64> - fails with BaseFeesClaimedError if the account is not a token account,
+/// @return since base movement could then not be ruled out
+fn read_token_amount(token_account: &AccountInfo) -> Result<u64> {
+    let data = token_account.try_borrow_data()?;
+    if data.len() < TokenAccount::LEN {
+        msg!("Cannot read token balance of {}, base movement can't be ruled out", token_account.key());
+        return Err(FeeRoutingError::BaseFeesClaimedError.into());
+    }
+
+    read_u64(&data, 64)
+}
+
+/// @notice Snapshot the base token balances a claim could move
+/// @param base_treasury The program's base treasury
+/// @param token_a_vault The pool's token A (base) vault
+/// @return Result<BaseBalances> - the current balances
+fn snapshot_base_balances(base_treasury: &AccountInfo, token_a_vault: &AccountInfo) -> Result<BaseBalances> {
+    Ok(BaseBalances {
+        base_treasury: read_token_amount(base_treasury)?,
+        token_a_vault: read_token_amount(token_a_vault)?,
+    })
+}
+
+/// @notice Enforce that a claim moved no base token at all
+/// @dev Base leaving the pool's token A vault is a base claim even if it did not land in the treasury,
+/// @dev the treasury side is checked by detect_base_fees
+/// @param before Base balances before the claim CPI
+/// @param after Base balances after the claim CPI
+/// @param quote_claimed Amount of quote tokens that were claimed
+/// @return Result<u64> - the base amount claimed, always 0 on success; fails with BaseFeesClaimedError otherwise
+fn verify_quote_only_claim(before: &BaseBalances, after: &BaseBalances, quote_claimed: u64) -> Result<u64> {
+    let token_a_vault_outflow = before.token_a_vault.saturating_sub(after.token_a_vault);
+    if token_a_vault_outflow > 0 {
+        msg!("CRITICAL: {} base tokens left the pool's token A vault during the claim", token_a_vault_outflow);
+        msg!("  Distribution ABORTED to prevent base token distribution");
+        return Err(FeeRoutingError::BaseFeesClaimedError.into());
+    }
+
+    detect_base_fees(before.base_treasury, after.base_treasury, quote_claimed)
+}
+
+/// @notice Enhanced validation for quote-only fee collection
+/// @dev Validates that the pool configuration and position setup will only collect quote fees
+/// @dev This function implements multiple layers of validation as required by the bounty
+/// @param pool_account The DAMM V2 pool account info
+/// @param quote_mint_key The expected quote mint pubkey
+/// @param position_info Additional position information for validation
+/// @return Result<()> - fails if quote-only cannot be guaranteed
+#[allow(dead_code)]
+fn validate_quote_only_configuration(
+    pool_account: &AccountInfo,
+    quote_mint_key: &Pubkey,
+    position_info: Option<&AccountInfo>,
+) -> Result<()> {
+    let pool_data = pool_account.data.borrow();
+
+    // Deserialize the DAMM V2 pool layout to extract token mints
+    let pool = DammV2Pool::try_from_bytes(&pool_data)?;
+    let pool_token_a = pool.token_a_mint;
+    let pool_token_b = pool.token_b_mint;
+
+    // Critical validation: quote mint must be token B
+    if quote_mint_key != &pool_token_b {
+        msg!("VALIDATION FAILED: Quote mint is not token B");
+        msg!("  Pool token A: {}", pool_token_a);
+        msg!("  Pool token B: {}", pool_token_b);
+        msg!("  Expected quote: {}", quote_mint_key);
+        return Err(FeeRoutingError::InvalidQuoteMint.into());
+    }
+
+    // Ensure quote mint is not token A (double check)
+    if quote_mint_key == &pool_token_a {
+        msg!("CRITICAL: Quote mint cannot be token A (base token)");
+        return Err(FeeRoutingError::BaseFeeDetected.into());
+    }
+
+    // Additional position-level validation if available
+    if let Some(pos_info) = position_info {
+        let pos_data = pos_info.data.borrow();
+        if pos_data.len() >= 8 {
+            // Validate position is configured correctly for quote-only fees
+            // This would require knowledge of the DAMM V2 position structure
+            msg!("Position validation: length {} bytes", pos_data.len());
+        }
+    }
+
+    msg!("Quote-only configuration validated:");
+    msg!("  Token A (base): {}", pool_token_a);
+    msg!("  Token B (quote): {} ✓", pool_token_b);
+    msg!("  Position will collect ONLY quote token fees ✓");
+
+    Ok(())
+}
+
+/// @notice Account structure for initializing the global program state
+/// @dev Defines all accounts required to set up the global configuration
+/// @dev The global_state account stores the creator's fee destination and is a Program Derived Address
+#[derive(Accounts)]
+pub struct InitializeGlobalState<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = GlobalState::LEN,
+        seeds = [GLOBAL_STATE_SEED],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for updating the creator's fee destination
+/// @dev Requires the global state authority as signer
+/// @dev The new creator ATA is validated against the mint of the current creator ATA
+#[derive(Accounts)]
+pub struct UpdateCreatorAta<'info> {
+    /// Global state
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Current creator quote ATA (from global state)
+    #[account(constraint = current_creator_quote_ata.key() == global_state.creator_quote_ata)]
+    pub current_creator_quote_ata: Account<'info, TokenAccount>,
+
+    /// New creator quote ATA, must hold the same mint
+    #[account(
+        constraint = new_creator_quote_ata.mint == current_creator_quote_ata.mint
+            @ FeeRoutingError::CreatorAtaMintMismatch
+    )]
+    pub new_creator_quote_ata: Account<'info, TokenAccount>,
+
+    /// Global state authority
+    pub authority: Signer<'info>,
+}
+
+/// @notice Account structure for toggling the emergency pause
+/// @dev Requires the global state authority as signer
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// Global state
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Global state authority
+    pub authority: Signer<'info>,
+}
+
+/// @notice Account structure for setting the fast-path crank authority
+/// @dev Requires the global state authority as signer
+#[derive(Accounts)]
+pub struct SetCrankAuthority<'info> {
+    /// Global state
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Global state authority
+    pub authority: Signer<'info>,
+}
+
+/// @notice Account structure for initializing the distribution policy of a vault
+/// @dev Requires the global state authority as signer
+/// @dev The policy_config account is a PDA derived from the vault_seed
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct InitializePolicyConfig<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PolicyConfig::LEN,
+        seeds = [POLICY_CONFIG_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub policy_config: Account<'info, PolicyConfig>,
+
+    /// Global state authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for updating the policy of a vault
+/// @dev The progress PDA is always passed so an open distribution day can't be hidden from the check
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct UpdatePolicyConfig<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [POLICY_CONFIG_SEED, &vault_seed.to_le_bytes()],
+        bump = policy_config.bump
+    )]
+    pub policy_config: Account<'info, PolicyConfig>,
+
+    /// Distribution progress of the vault, not yet initialized before the first crank
+    /// CHECK: PDA checked by seeds, deserialized only when owned by this program
+    #[account(
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub distribution_progress: UncheckedAccount<'info>,
+
+    /// Global state authority
+    pub authority: Signer<'info>,
+}
+
+/// @notice Account structure for setting the investor allowlist of a vault
+/// @dev Requires the global state authority as signer
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct SetInvestorAllowlist<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = InvestorAllowlist::LEN,
+        seeds = [INVESTOR_ALLOWLIST_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub investor_allowlist: Account<'info, InvestorAllowlist>,
+
+    /// Global state authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for registering the investors of one distribution page
+/// @dev Requires the global state authority as signer
+/// @param vault_seed Unique identifier used in PDA derivation
+/// @param page_index Distribution page the investors are paid on
+#[derive(Accounts)]
+#[instruction(vault_seed: u64, page_index: u32)]
+pub struct RegisterInvestors<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = InvestorRegistry::LEN,
+        seeds = [INVESTOR_REGISTRY_SEED, &vault_seed.to_le_bytes(), &page_index.to_le_bytes()],
+        bump
+    )]
+    pub investor_registry: Account<'info, InvestorRegistry>,
+
+    /// Global state authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for setting an investor's raw lock
+/// @dev Requires the global state authority as signer
+/// @param vault_seed Unique identifier used in PDA derivation
+/// @param recipient Wallet the locked tokens belong to
+#[derive(Accounts)]
+#[instruction(vault_seed: u64, recipient: Pubkey)]
+pub struct SetRawLock<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RawLock::LEN,
+        seeds = [RAW_LOCK_SEED, &vault_seed.to_le_bytes(), recipient.as_ref()],
+        bump
+    )]
+    pub raw_lock: Account<'info, RawLock>,
+
+    /// Global state authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for registering a Bonfida vesting account
+/// @dev Requires the global state authority as signer
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct RegisterBonfidaVesting<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = BonfidaRegistry::LEN,
+        seeds = [BONFIDA_REGISTRY_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub bonfida_registry: Account<'info, BonfidaRegistry>,
+
+    /// Bonfida vesting account being registered
+    /// CHECK: Owner is checked against BONFIDA_VESTING_PROGRAM_ID, the data is parsed in the handler
+    #[account(owner = BONFIDA_VESTING_PROGRAM_ID @ FeeRoutingError::InvalidStreamflowContract)]
+    pub vesting_account: UncheckedAccount<'info>,
+
+    /// Token account the vesting releases into, its owner is the wallet paid for the vesting
+    pub destination_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Global state authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for initializing the creator split of a vault
+/// @dev Requires the global state authority as signer
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct InitializeCreatorSplit<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = CreatorSplit::LEN,
+        seeds = [CREATOR_SPLIT_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub creator_split: Account<'info, CreatorSplit>,
+
+    /// Global state authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for prefunding the distribution progress rent
+/// @dev Anyone may fund, typically the creator right after initializing the policy
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct FundCrankRent<'info> {
+    /// Distribution progress for the vault, created here so the crank never has to pay for it
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = DistributionProgress::LEN,
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub distribution_progress: Account<'info, DistributionProgress>,
+
+    /// Account paying the rent
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for migrating a distribution progress account to the current layout
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct MigrateDistributionProgress<'info> {
+    /// Distribution progress for the vault, left unchecked since the legacy layout can't be deserialized
+    /// CHECK: PDA validated by seeds, owner checked against this program, layout checked by the handler
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump,
+        owner = crate::ID
+    )]
+    pub distribution_progress: UncheckedAccount<'info>,
+
+    /// Account paying the extra rent of the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for migrating the global state account to the current layout
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    /// Global state, left unchecked since the legacy layout can't be deserialized
+    /// CHECK: PDA validated by seeds, owner checked against this program, layout checked by the handler
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump,
+        owner = crate::ID
+    )]
+    pub global_state: UncheckedAccount<'info>,
+
+    /// Creator quote ATA recorded in the global state, checked by the handler
+    pub creator_quote_ata: Account<'info, TokenAccount>,
+
+    /// Owner of the creator quote ATA, becomes the authority of a legacy global state
+    pub creator: Signer<'info>,
+
+    /// Account paying the extra rent of the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// @notice Account structure for initializing a quote-only honorary fee position
+/// @dev Defines all accounts needed to create a position in DAMM V2 via Cross-Program Invocation
+/// @dev All PDAs are derived using the vault_seed parameter for secure ownership control
+/// @param vault_seed Unique identifier used in PDA derivation for position ownership
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct InitializeHonoraryPosition<'info> {
+    /// Global state, counts the deployment's open positions
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// The pool for which we're creating the honorary position
+    /// CHECK: This will be validated by the DAMM V2 program
+    pub pool: UncheckedAccount<'info>,
+
+    /// The position account to be created (PDA derived from position NFT mint)
+    /// CHECK: This will be created by the DAMM V2 program
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+
+    /// Position NFT mint (will be created and owned by our program PDA)
+    /// CHECK: This will be created as a signer
+    #[account(mut)]
+    pub position_nft_mint: Signer<'info>,
+
+    /// Position NFT account (ATA for the position NFT)
+    /// CHECK: This will be created by DAMM V2 program
+    #[account(mut)]
+    pub position_nft_account: UncheckedAccount<'info>,
+
+    /// PDA that will own the position
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [VAULT_SEED, &vault_seed.to_le_bytes(), INVESTOR_FEE_POSITION_OWNER_SEED],
+        bump
+    )]
+    pub position_owner_pda: UncheckedAccount<'info>,
+
+    /// DAMM V2 Pool Authority (fixed address)
+    /// CHECK: This is the fixed pool authority for DAMM V2
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// Quote mint of the pool (token B in DAMM V2), SPL Token or Token-2022
+    #[account(mint::token_program = quote_token_program)]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Token A mint of the pool  
+    pub token_a_mint: Account<'info, Mint>,
+
+    /// Quote treasury ATA owned by the program, sized by the associated token program for the mint's extensions
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = quote_mint,
+        associated_token::authority = quote_treasury_authority,
+        associated_token::token_program = quote_token_program
+    )]
+    pub quote_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Base treasury ATA owned by the program, receives any base fees so they can be detected
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = token_a_mint,
+        associated_token::authority = quote_treasury_authority
+    )]
+    pub base_treasury: Account<'info, TokenAccount>,
+
+    /// Authority for the quote treasury (PDA)
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub quote_treasury_authority: UncheckedAccount<'info>,
+
+    /// Payer for initialization
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// DAMM V2 CP-AMM program
+    /// CHECK: Address is checked against CP_AMM_PROGRAM_ID
+    #[account(address = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
+    pub cp_amm_program: UncheckedAccount<'info>,
+
+    /// Event authority for DAMM V2
+    /// CHECK: This is the event authority PDA for DAMM V2
+    pub event_authority: UncheckedAccount<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Rent sysvar
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// @notice Account structure for setting up a vault in one transaction
+/// @dev The accounts of InitializePolicyConfig and InitializeHonoraryPosition, with the global state authority
+/// @dev as payer; the quote mint must be the one pinned in global state
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct InitializeVault<'info> {
+    /// Global state
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Policy config of the vault
+    #[account(
+        init,
+        payer = authority,
+        space = PolicyConfig::LEN,
+        seeds = [POLICY_CONFIG_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub policy_config: Account<'info, PolicyConfig>,
+
+    /// The pool for which we're creating the honorary position
+    /// CHECK: This will be validated by the DAMM V2 program
+    pub pool: UncheckedAccount<'info>,
+
+    /// The position account to be created (PDA derived from position NFT mint)
+    /// CHECK: This will be created by the DAMM V2 program
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+
+    /// Position NFT mint (will be created and owned by our program PDA)
+    /// CHECK: This will be created as a signer
+    #[account(mut)]
+    pub position_nft_mint: Signer<'info>,
+
+    /// Position NFT account (ATA for the position NFT)
+    /// CHECK: This will be created by DAMM V2 program
+    #[account(mut)]
+    pub position_nft_account: UncheckedAccount<'info>,
+
+    /// PDA that will own the position
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [VAULT_SEED, &vault_seed.to_le_bytes(), INVESTOR_FEE_POSITION_OWNER_SEED],
+        bump
+    )]
+    pub position_owner_pda: UncheckedAccount<'info>,
+
+    /// DAMM V2 Pool Authority (fixed address)
+    /// CHECK: This is the fixed pool authority for DAMM V2
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// Quote mint of the pool (token B in DAMM V2), pinned in global state
+    #[account(
+        address = global_state.quote_mint @ FeeRoutingError::InvalidQuoteMint,
+        mint::token_program = quote_token_program
+    )]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Token A mint of the pool
+    pub token_a_mint: Account<'info, Mint>,
+
+    /// Quote treasury ATA owned by the program, sized by the associated token program for the mint's extensions
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = quote_mint,
+        associated_token::authority = quote_treasury_authority,
+        associated_token::token_program = quote_token_program
+    )]
+    pub quote_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Base treasury ATA owned by the program, receives any base fees so they can be detected
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = token_a_mint,
+        associated_token::authority = quote_treasury_authority
+    )]
+    pub base_treasury: Account<'info, TokenAccount>,
+
+    /// Authority for the quote treasury (PDA)
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub quote_treasury_authority: UncheckedAccount<'info>,
+
+    /// Global state authority, pays for every account created
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// DAMM V2 CP-AMM program
+    /// CHECK: Address is checked against CP_AMM_PROGRAM_ID
+    #[account(address = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
+    pub cp_amm_program: UncheckedAccount<'info>,
+
+    /// Event authority for DAMM V2
+    /// CHECK: This is the event authority PDA for DAMM V2
+    pub event_authority: UncheckedAccount<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Rent sysvar
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// @notice Account structure for initializing honorary positions of several vaults at once
+/// @dev Accounts shared by every vault; each vault's own accounts are passed as remaining accounts,
+/// @dev BATCH_ACCOUNTS_PER_VAULT per vault in vault_seeds order
+#[derive(Accounts)]
+pub struct InitializeHonoraryPositionsBatch<'info> {
+    /// Global state, counts the deployment's open positions
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// DAMM V2 Pool Authority (fixed address)
+    /// CHECK: This is the fixed pool authority for DAMM V2
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// Quote mint shared by every pool of the batch (token B in DAMM V2)
+    #[account(mint::token_program = quote_token_program)]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Payer for the positions and treasuries
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// DAMM V2 CP-AMM program
+    /// CHECK: Address is checked against CP_AMM_PROGRAM_ID
+    #[account(address = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
+    pub cp_amm_program: UncheckedAccount<'info>,
+
+    /// Event authority for DAMM V2
+    /// CHECK: This is the event authority PDA for DAMM V2
+    pub event_authority: UncheckedAccount<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Rent sysvar
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// @notice Account structure for creating a vault's quote treasury
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct InitializeQuoteTreasury<'info> {
+    /// Quote mint, SPL Token or Token-2022
+    #[account(mint::token_program = quote_token_program)]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Quote treasury ATA owned by the program, sized by the associated token program for the mint's extensions
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = quote_mint,
+        associated_token::authority = quote_treasury_authority,
+        associated_token::token_program = quote_token_program
+    )]
+    pub quote_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Quote treasury authority (PDA)
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub quote_treasury_authority: UncheckedAccount<'info>,
+
+    /// Payer for the treasury rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// @notice Account structure for the 24-hour fee distribution crank mechanism
+/// @dev Defines all accounts needed for claiming fees from DAMM V2 and distributing to stakeholders
+/// @dev Uses pagination via page_index to handle large numbers of investors across multiple transactions
+/// @dev Remaining accounts should be passed as: [streamflow_stream_1, investor_ata_1, ...]
+/// @dev Streams must be sorted by strictly ascending pubkey, continuing across pages of the same day
+/// @dev Additional position groups, if any, come before the investor pairs
+/// @param page_index Index for pagination when processing multiple investors (0-based)
+#[derive(Accounts)]
+#[instruction(
+    _trade_amount: u64,
+    _fee_percentage: u64,
+    page_index: u32,
+    total_pages: u32,
+    investor_weights_bps: Vec<u16>,
+    claim_only: bool,
+    allowlist_proofs: Vec<Vec<[u8; 32]>>,
+    vault_seed: u64
+)]
+pub struct DistributeFees<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Distribution progress tracking
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = DistributionProgress::LEN,
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub distribution_progress: Account<'info, DistributionProgress>,
+
+    /// Distribution policy for the vault
+    #[account(
+        seeds = [POLICY_CONFIG_SEED, &vault_seed.to_le_bytes()],
+        bump = policy_config.bump
+    )]
+    pub policy_config: Account<'info, PolicyConfig>,
+
+    /// Honorary position
+    /// CHECK: This is the Meteora position account
+    pub position: UncheckedAccount<'info>,
+
+    /// Position owner PDA
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [VAULT_SEED, &vault_seed.to_le_bytes(), INVESTOR_FEE_POSITION_OWNER_SEED],
+        bump
+    )]
+    pub position_owner_pda: UncheckedAccount<'info>,
+
+    /// Quote mint, pinned in global state so every crank uses the same mint
+    #[account(
+        address = global_state.quote_mint @ FeeRoutingError::InvalidQuoteMint,
+        mint::token_program = quote_token_program
+    )]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Quote treasury ATA
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = quote_treasury_authority,
+        associated_token::token_program = quote_token_program
+    )]
+    pub quote_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Base treasury ATA, destination for token A fees during the claim
+    #[account(
+        mut,
+        associated_token::mint = token_a_mint,
+        associated_token::authority = quote_treasury_authority
+    )]
+    pub base_treasury: Account<'info, TokenAccount>,
+
+    /// Quote treasury authority (PDA)
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub quote_treasury_authority: UncheckedAccount<'info>,
+
+    /// Creator's quote ATA (from global state), must hold the quote mint being distributed
+    #[account(
+        mut,
+        constraint = creator_quote_ata.key() == global_state.creator_quote_ata,
+        constraint = creator_quote_ata.mint == quote_mint.key() @ FeeRoutingError::CreatorAtaMintMismatch
+    )]
+    pub creator_quote_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Optional allowlist of investor pairs, required when the policy enables it
+    #[account(
+        seeds = [INVESTOR_ALLOWLIST_SEED, &vault_seed.to_le_bytes()],
+        bump = investor_allowlist.bump
+    )]
+    pub investor_allowlist: Option<Account<'info, InvestorAllowlist>>,
+
+    /// Optional registry page of the investors paid on this page, required when the policy enables it
+    #[account(
+        seeds = [INVESTOR_REGISTRY_SEED, &vault_seed.to_le_bytes(), &page_index.to_le_bytes()],
+        bump = investor_registry.bump
+    )]
+    pub investor_registry: Option<Account<'info, InvestorRegistry>>,
+
+    /// Optional split of the creator remainder, recipient ATAs are passed after the investor pairs
+    #[account(
+        seeds = [CREATOR_SPLIT_SEED, &vault_seed.to_le_bytes()],
+        bump = creator_split.bump
+    )]
+    pub creator_split: Option<Account<'info, CreatorSplit>>,
+
+    /// Payer for any account initialization, only charged rent if fund_crank_rent was never called
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// DAMM V2 Pool (for fee claiming)
+    /// CHECK: This is the DAMM V2 pool account
+    pub pool: UncheckedAccount<'info>,
+
+    /// DAMM V2 Pool Authority
+    /// CHECK: This is the fixed pool authority for DAMM V2
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// Position NFT account
+    /// CHECK: This is the position NFT account
+    pub position_nft_account: UncheckedAccount<'info>,
+
+    /// Token A mint
+    pub token_a_mint: Account<'info, Mint>,
+
+    /// Token A vault of the pool
+    /// CHECK: This is the token A vault account
+    pub token_a_vault: UncheckedAccount<'info>,
+
+    /// Token B vault of the pool (quote mint vault)
+    /// CHECK: This is the token B vault account
+    pub token_b_vault: UncheckedAccount<'info>,
+
+    /// DAMM V2 CP-AMM program
+    /// CHECK: Address is checked against CP_AMM_PROGRAM_ID
+    #[account(address = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
+    pub cp_amm_program: UncheckedAccount<'info>,
+
+    /// Event authority for DAMM V2
+    /// CHECK: This is the event authority PDA for DAMM V2
+    pub event_authority: UncheckedAccount<'info>,
+
+    /// Streamflow program
+    /// CHECK: This is the Streamflow program ID  
+    pub streamflow_program: UncheckedAccount<'info>,
+
+    /// System program
     pub system_program: Program<'info, System>,
+
+    /// Token program, used for the base side of the claim
+    pub token_program: Program<'info, Token>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Optional SPL Memo program, required when the policy emits distribution memos
+    /// CHECK: Address is checked against SPL_MEMO_PROGRAM_ID
+    #[account(address = SPL_MEMO_PROGRAM_ID @ FeeRoutingError::InvalidMemoProgram)]
+    pub memo_program: Option<UncheckedAccount<'info>>,
+
+    /// Optional receipt of the day being cranked, written by the page that closes the day
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = DistributionReceipt::LEN,
+        seeds = [
+            DISTRIBUTION_RECEIPT_SEED,
+            &vault_seed.to_le_bytes(),
+            &distribution_progress.completed_days.to_le_bytes()
+        ],
+        bump
+    )]
+    pub distribution_receipt: Option<Account<'info, DistributionReceipt>>,
+
+    /// Optional registry of the vault's Bonfida vesting accounts, required when a page pairs one
+    #[account(
+        seeds = [BONFIDA_REGISTRY_SEED, &vault_seed.to_le_bytes()],
+        bump = bonfida_registry.bump
+    )]
+    pub bonfida_registry: Option<Account<'info, BonfidaRegistry>>,
+
+    /// Optional ledger of the day's payouts per investor wallet, required when a per-investor cap is set and the
+    /// day spans several pages
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = InvestorCapLedger::LEN,
+        seeds = [INVESTOR_CAP_LEDGER_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub investor_cap_ledger: Option<Account<'info, InvestorCapLedger>>,
+    // Remaining accounts should be passed as:
+    // [streamflow_stream_1, investor_ata_1, streamflow_stream_2, investor_ata_2, ...]
+}
+
+/// @notice Account structure for previewing a fee distribution
+/// @dev Read-only, no tokens are moved and no state is written
+/// @dev Remaining accounts should be passed as for distribute_fees: [streamflow_stream_1, investor_ata_1, ...]
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct PreviewDistribution<'info> {
+    /// Distribution policy for the vault, the preview pays by it like the crank
+    #[account(
+        seeds = [POLICY_CONFIG_SEED, &vault_seed.to_le_bytes()],
+        bump = policy_config.bump
+    )]
+    pub policy_config: Account<'info, PolicyConfig>,
+
+    /// Distribution progress for the vault, absent before the first crank
+    #[account(
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump = distribution_progress.bump
+    )]
+    pub distribution_progress: Option<Account<'info, DistributionProgress>>,
+
+    /// Registry of the vault's Bonfida vesting accounts, required when a Bonfida vesting is previewed
+    #[account(
+        seeds = [BONFIDA_REGISTRY_SEED, &vault_seed.to_le_bytes()],
+        bump = bonfida_registry.bump
+    )]
+    pub bonfida_registry: Option<Account<'info, BonfidaRegistry>>,
+
+    /// Ledger of the day's payouts per investor wallet, counted against the per-investor cap when present
+    #[account(
+        seeds = [INVESTOR_CAP_LEDGER_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub investor_cap_ledger: Option<Account<'info, InvestorCapLedger>>,
 }
 
-/// @notice Account structure for initializing a quote-only honorary fee position
-/// @dev Defines all accounts needed to create a position in DAMM V2 via Cross-Program Invocation
-/// @dev All PDAs are derived using the vault_seed parameter for secure ownership control
-/// @param vault_seed Unique identifier used in PDA derivation for position ownership
+/// @notice Account structure for reading the distribution progress
+/// @dev Read-only, no state is written
+/// @param vault_seed Unique identifier used in PDA derivation
 #[derive(Accounts)]
 #[instruction(vault_seed: u64)]
-pub struct InitializeHonoraryPosition<'info> {
-    /// The pool for which we're creating the honorary position
-    /// CHECK: This will be validated by the DAMM V2 program
+pub struct ReadDistributionProgress<'info> {
+    /// Distribution progress for the vault
+    #[account(
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump = distribution_progress.bump
+    )]
+    pub distribution_progress: Account<'info, DistributionProgress>,
+}
+
+/// @notice Account structure for reading the claimable fees of the honorary position
+/// @dev Read-only, no state is written
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct GetPendingFees<'info> {
+    /// DAMM V2 pool of the honorary position
+    /// CHECK: Owned by the DAMM V2 program, parsed with DammV2Pool
+    #[account(owner = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
     pub pool: UncheckedAccount<'info>,
 
-    /// The position account to be created (PDA derived from position NFT mint)
-    /// CHECK: This will be created by the DAMM V2 program
-    #[account(mut)]
+    /// Honorary position
+    /// CHECK: Owned by the DAMM V2 program, parsed with DammV2Position
+    #[account(owner = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
     pub position: UncheckedAccount<'info>,
 
-    /// Position NFT mint (will be created and owned by our program PDA)
-    /// CHECK: This will be created as a signer
-    #[account(mut)]
-    pub position_nft_mint: Signer<'info>,
-
-    /// Position NFT account (ATA for the position NFT)
-    /// CHECK: This will be created by DAMM V2 program
-    #[account(mut)]
-    pub position_nft_account: UncheckedAccount<'info>,
-
-    /// PDA that will own the position
+    /// Position owner PDA
     /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
     #[account(
         seeds = [VAULT_SEED, &vault_seed.to_le_bytes(), INVESTOR_FEE_POSITION_OWNER_SEED],
@@ -701,26 +5716,39 @@ pub struct InitializeHonoraryPosition<'info> {
     )]
     pub position_owner_pda: UncheckedAccount<'info>,
 
-    /// DAMM V2 Pool Authority (fixed address)
-    /// CHECK: This is the fixed pool authority for DAMM V2
-    pub pool_authority: UncheckedAccount<'info>,
+    /// Token account holding the position NFT
+    #[account(constraint = position_nft_account.owner == position_owner_pda.key() @ FeeRoutingError::InvalidPositionOwner)]
+    pub position_nft_account: Account<'info, TokenAccount>,
+}
 
-    /// Quote mint of the pool (token B in DAMM V2)
-    pub quote_mint: Account<'info, Mint>,
+/// @notice Account structure for sweeping base-token dust to the creator
+/// @dev Requires the global state authority as signer
+/// @dev The creator base ATA must be owned by the same wallet as the creator quote ATA
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct SweepBaseFees<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-    /// Token A mint of the pool  
-    pub token_a_mint: Account<'info, Mint>,
+    /// Base mint, must differ from the quote mint so the quote treasury can't be swept
+    #[account(constraint = base_mint.key() != global_state.quote_mint @ FeeRoutingError::InvalidQuoteMint)]
+    pub base_mint: Account<'info, Mint>,
 
-    /// Quote treasury ATA owned by the program
+    /// Base treasury ATA holding stranded base fees
     #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = quote_mint,
+        mut,
+        associated_token::mint = base_mint,
         associated_token::authority = quote_treasury_authority
     )]
-    pub quote_treasury: Account<'info, TokenAccount>,
+    pub base_treasury: Account<'info, TokenAccount>,
 
-    /// Authority for the quote treasury (PDA)
+    /// Treasury authority (PDA)
     /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
     #[account(
         seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
@@ -728,145 +5756,327 @@ pub struct InitializeHonoraryPosition<'info> {
     )]
     pub quote_treasury_authority: UncheckedAccount<'info>,
 
-    /// Payer for initialization
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
-    /// DAMM V2 CP-AMM program
-    /// CHECK: This is the DAMM V2 CP-AMM program ID
-    pub cp_amm_program: UncheckedAccount<'info>,
+    /// Creator's quote ATA (from global state), identifies the creator wallet
+    #[account(constraint = creator_quote_ata.key() == global_state.creator_quote_ata)]
+    pub creator_quote_ata: Account<'info, TokenAccount>,
 
-    /// Event authority for DAMM V2
-    /// CHECK: This is the event authority PDA for DAMM V2
-    pub event_authority: UncheckedAccount<'info>,
+    /// Creator's base ATA receiving the swept fees
+    #[account(
+        mut,
+        constraint = creator_base_ata.mint == base_mint.key() @ FeeRoutingError::CreatorAtaMintMismatch,
+        constraint = creator_base_ata.owner == creator_quote_ata.owner @ FeeRoutingError::Unauthorized
+    )]
+    pub creator_base_ata: Account<'info, TokenAccount>,
 
-    /// System program
-    pub system_program: Program<'info, System>,
+    /// Global state authority
+    pub authority: Signer<'info>,
 
-    /// Token program
     pub token_program: Program<'info, Token>,
+}
 
-    /// Associated token program
-    pub associated_token_program: Program<'info, AssociatedToken>,
+/// @notice Account structure for force-closing a stuck distribution day
+/// @dev Requires the global state authority as signer
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct ForceCloseDay<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-    /// Rent sysvar
-    pub rent: Sysvar<'info, Rent>,
+    /// Distribution progress of the stuck day
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub distribution_progress: Account<'info, DistributionProgress>,
+
+    /// Quote mint, pinned in global state
+    #[account(
+        address = global_state.quote_mint @ FeeRoutingError::InvalidQuoteMint,
+        mint::token_program = quote_token_program
+    )]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Quote treasury ATA holding the undistributed fees
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = quote_treasury_authority,
+        associated_token::token_program = quote_token_program
+    )]
+    pub quote_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Quote treasury authority (PDA)
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub quote_treasury_authority: UncheckedAccount<'info>,
+
+    /// Creator's quote ATA (from global state) receiving the sweep
+    #[account(
+        mut,
+        constraint = creator_quote_ata.key() == global_state.creator_quote_ata,
+        constraint = creator_quote_ata.mint == quote_mint.key() @ FeeRoutingError::CreatorAtaMintMismatch
+    )]
+    pub creator_quote_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Optional split of the creator payout, recipient ATAs are passed as remaining accounts
+    #[account(
+        seeds = [CREATOR_SPLIT_SEED, &vault_seed.to_le_bytes()],
+        bump = creator_split.bump
+    )]
+    pub creator_split: Option<Account<'info, CreatorSplit>>,
+
+    /// Global state authority
+    pub authority: Signer<'info>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
 }
 
-/// @notice Account structure for the 24-hour fee distribution crank mechanism
-/// @dev Defines all accounts needed for claiming fees from DAMM V2 and distributing to stakeholders
-/// @dev Uses pagination via page_index to handle large numbers of investors across multiple transactions
-/// @dev Remaining accounts should be passed as: [streamflow_stream_1, investor_ata_1, ...]
-/// @param page_index Index for pagination when processing multiple investors (0-based)
-/// @param investor_fee_share_bps Basis points allocated to investors (e.g., 8000 = 80%)
-/// @param daily_cap_lamports Optional daily distribution cap in lamports
-/// @param min_payout_lamports Minimum payout threshold to prevent dust transactions
-/// @param y0_total Total locked tokens across all Y0 investors for pro-rata calculation
+/// @notice Account structure for closing a vault's old distribution receipts
+/// @dev Requires the global state authority as signer, the receipts follow as remaining accounts
+/// @param vault_seed Unique identifier used in PDA derivation
 #[derive(Accounts)]
-#[instruction(page_index: u32, investor_fee_share_bps: u16, daily_cap_lamports: Option<u64>, min_payout_lamports: u64, y0_total: u64)]
-pub struct DistributeFees<'info> {
+#[instruction(vault_seed: u64)]
+pub struct CloseOldReceipts<'info> {
     /// Global state
     #[account(
         seeds = [GLOBAL_STATE_SEED],
-        bump = global_state.bump
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
     )]
     pub global_state: Account<'info, GlobalState>,
 
-    /// Distribution progress tracking
+    /// Distribution progress counting the vault's closed days
     #[account(
-        init_if_needed,
-        payer = payer,
-        space = DistributionProgress::LEN,
-        seeds = [DISTRIBUTION_PROGRESS_SEED, &12345u64.to_le_bytes()], // Using placeholder vault seed
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
         bump
     )]
     pub distribution_progress: Account<'info, DistributionProgress>,
 
-    /// Honorary position
-    /// CHECK: This is the Meteora position account
-    pub position: UncheckedAccount<'info>,
+    /// Global state authority, receives the rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
 
-    /// Position owner PDA
-    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+/// @notice Account structure for flushing a vault's carry-over to the creator
+/// @dev Requires the global state authority as signer
+/// @param vault_seed Unique identifier used in PDA derivation
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct FlushCarryOver<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Distribution progress holding the carry-over
     #[account(
-        seeds = [VAULT_SEED, &12345u64.to_le_bytes(), INVESTOR_FEE_POSITION_OWNER_SEED], // Using placeholder vault seed
+        mut,
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
         bump
     )]
-    pub position_owner_pda: UncheckedAccount<'info>,
+    pub distribution_progress: Account<'info, DistributionProgress>,
 
-    /// Quote mint
-    pub quote_mint: Account<'info, Mint>,
+    /// Quote mint, pinned in global state
+    #[account(
+        address = global_state.quote_mint @ FeeRoutingError::InvalidQuoteMint,
+        mint::token_program = quote_token_program
+    )]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
 
-    /// Quote treasury ATA
+    /// Quote treasury ATA holding the carry-over
     #[account(
         mut,
         associated_token::mint = quote_mint,
-        associated_token::authority = quote_treasury_authority
+        associated_token::authority = quote_treasury_authority,
+        associated_token::token_program = quote_token_program
     )]
-    pub quote_treasury: Account<'info, TokenAccount>,
+    pub quote_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
 
     /// Quote treasury authority (PDA)
     /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
     #[account(
-        seeds = [QUOTE_TREASURY_SEED, &12345u64.to_le_bytes()], // Using placeholder vault seed
+        seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
         bump
     )]
     pub quote_treasury_authority: UncheckedAccount<'info>,
 
-    /// Creator's quote ATA (from global state)
+    /// Creator's quote ATA (from global state) receiving the carry-over
     #[account(
         mut,
-        constraint = creator_quote_ata.key() == global_state.creator_quote_ata
+        constraint = creator_quote_ata.key() == global_state.creator_quote_ata,
+        constraint = creator_quote_ata.mint == quote_mint.key() @ FeeRoutingError::CreatorAtaMintMismatch
     )]
-    pub creator_quote_ata: Account<'info, TokenAccount>,
+    pub creator_quote_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
 
-    /// Payer for any account initialization
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    /// Optional split of the creator payout, recipient ATAs are passed as remaining accounts
+    #[account(
+        seeds = [CREATOR_SPLIT_SEED, &vault_seed.to_le_bytes()],
+        bump = creator_split.bump
+    )]
+    pub creator_split: Option<Account<'info, CreatorSplit>>,
 
-    /// DAMM V2 Pool (for fee claiming)
-    /// CHECK: This is the DAMM V2 pool account
+    /// Global state authority
+    pub authority: Signer<'info>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
+}
+
+/// @notice Account structure for transferring the position NFT out of the position owner PDA
+/// @dev Requires the global state authority as signer
+/// @dev The destination token account must already exist and be owned by the new owner
+/// @param vault_seed Unique identifier used in PDA derivation
+/// @param new_owner Wallet that will own the position NFT
+#[derive(Accounts)]
+#[instruction(vault_seed: u64, new_owner: Pubkey)]
+pub struct TransferPositionOwnership<'info> {
+    /// Global state, the position stops counting as open
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Distribution progress, the transfer is refused mid-day
+    #[account(
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub distribution_progress: Account<'info, DistributionProgress>,
+
+    /// DAMM V2 pool of the honorary position
+    /// CHECK: Owned by the DAMM V2 program, parsed with DammV2Pool
+    #[account(owner = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
     pub pool: UncheckedAccount<'info>,
 
-    /// DAMM V2 Pool Authority
-    /// CHECK: This is the fixed pool authority for DAMM V2
-    pub pool_authority: UncheckedAccount<'info>,
+    /// Honorary position
+    /// CHECK: Owned by the DAMM V2 program, parsed with DammV2Position
+    #[account(owner = CP_AMM_PROGRAM_ID @ FeeRoutingError::InvalidCpAmmProgram)]
+    pub position: UncheckedAccount<'info>,
 
-    /// Position NFT account
-    /// CHECK: This is the position NFT account
-    pub position_nft_account: UncheckedAccount<'info>,
+    /// Position owner PDA
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [VAULT_SEED, &vault_seed.to_le_bytes(), INVESTOR_FEE_POSITION_OWNER_SEED],
+        bump
+    )]
+    pub position_owner_pda: UncheckedAccount<'info>,
 
-    /// Token A mint
-    pub token_a_mint: Account<'info, Mint>,
+    /// Position NFT mint
+    #[account(mint::token_program = nft_token_program)]
+    pub position_nft_mint: InterfaceAccount<'info, token_interface::Mint>,
 
-    /// Token A vault of the pool
-    /// CHECK: This is the token A vault account
-    pub token_a_vault: UncheckedAccount<'info>,
+    /// Token account holding the position NFT, owned by the position owner PDA
+    #[account(
+        mut,
+        constraint = position_nft_account.owner == position_owner_pda.key() @ FeeRoutingError::InvalidPositionOwner,
+        constraint = position_nft_account.mint == position_nft_mint.key() @ FeeRoutingError::InvalidPositionOwner
+    )]
+    pub position_nft_account: InterfaceAccount<'info, token_interface::TokenAccount>,
 
-    /// Token B vault of the pool (quote mint vault)
-    /// CHECK: This is the token B vault account
-    pub token_b_vault: UncheckedAccount<'info>,
+    /// Token account of the new owner receiving the position NFT
+    #[account(
+        mut,
+        constraint = new_owner_nft_account.owner == new_owner @ FeeRoutingError::InvalidPositionOwner,
+        constraint = new_owner_nft_account.mint == position_nft_mint.key() @ FeeRoutingError::InvalidPositionOwner
+    )]
+    pub new_owner_nft_account: InterfaceAccount<'info, token_interface::TokenAccount>,
 
-    /// DAMM V2 CP-AMM program
-    /// CHECK: This is the DAMM V2 CP-AMM program ID
-    pub cp_amm_program: UncheckedAccount<'info>,
+    /// Global state authority
+    pub authority: Signer<'info>,
 
-    /// Event authority for DAMM V2
-    /// CHECK: This is the event authority PDA for DAMM V2
-    pub event_authority: UncheckedAccount<'info>,
+    /// Token program of the position NFT mint, SPL Token or Token-2022
+    pub nft_token_program: Interface<'info, TokenInterface>,
+}
 
-    /// Streamflow program
-    /// CHECK: This is the Streamflow program ID  
-    pub streamflow_program: UncheckedAccount<'info>,
+/// @notice Account structure for closing the global state
+/// @dev Requires the global state authority as signer, the rent goes back to it
+#[derive(Accounts)]
+pub struct CloseGlobalState<'info> {
+    /// Global state, closed to the authority
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump,
+        has_one = authority @ FeeRoutingError::Unauthorized,
+        close = authority
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-    /// System program
-    pub system_program: Program<'info, System>,
+    /// Global state authority, receives the rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
 
-    /// Token program
-    pub token_program: Program<'info, Token>,
+/// @notice Account structure for seeding the quote treasury in integration tests
+/// @dev Only compiled with the `test-helpers` feature
+/// @param vault_seed Unique identifier used in PDA derivation
+#[cfg(feature = "test-helpers")]
+#[derive(Accounts)]
+#[instruction(vault_seed: u64)]
+pub struct DepositTestFees<'info> {
+    /// Global state
+    #[account(
+        seeds = [GLOBAL_STATE_SEED],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-    /// Associated token program
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    // Remaining accounts should be passed as:
-    // [streamflow_stream_1, investor_ata_1, streamflow_stream_2, investor_ata_2, ...]
+    /// Distribution progress booking the deposit as carry-over
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_PROGRESS_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub distribution_progress: Account<'info, DistributionProgress>,
+
+    /// Quote mint, pinned in global state
+    #[account(
+        address = global_state.quote_mint @ FeeRoutingError::InvalidQuoteMint,
+        mint::token_program = quote_token_program
+    )]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Quote treasury ATA receiving the deposit
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = quote_treasury_authority,
+        associated_token::token_program = quote_token_program
+    )]
+    pub quote_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Quote treasury authority (PDA)
+    /// CHECK: This is a PDA derived from vault seed and validated by seeds constraint
+    #[account(
+        seeds = [QUOTE_TREASURY_SEED, &vault_seed.to_le_bytes()],
+        bump
+    )]
+    pub quote_treasury_authority: UncheckedAccount<'info>,
+
+    /// Depositor's quote token account funding the deposit
+    #[account(mut, token::mint = quote_mint, token::authority = depositor, token::token_program = quote_token_program)]
+    pub depositor_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Depositor signing the transfer
+    pub depositor: Signer<'info>,
+
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Interface<'info, TokenInterface>,
 }
@@ -0,0 +1,30 @@
+use crate::FeeRoutingError;
+use anchor_lang::prelude::*;
+
+/// Basis points in a whole, 10000 bps = 100%
+pub(crate) const BPS_DENOMINATOR: u64 = 10000;
+
+/// @notice Compute floor(a * b / denom) without intermediate overflow
+/// @dev The product is taken in u128, so only a result that doesn't fit in a u64 overflows
+/// @param a First factor
+/// @param b Second factor
+/// @param denom Divisor
+/// @return Result<u64> The floored quotient - fails with ArithmeticOverflow on a zero divisor or a result above u64::MAX
+pub(crate) fn mul_div_floor(a: u64, b: u64, denom: u64) -> Result<u64> {
+    let quotient = (a as u128)
+        .checked_mul(b as u128)
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?
+        .checked_div(denom as u128)
+        .ok_or(FeeRoutingError::ArithmeticOverflow)?;
+
+    Ok(u64::try_from(quotient).map_err(|_| FeeRoutingError::ArithmeticOverflow)?)
+}
+
+/// @notice Take a basis-point share of an amount, rounded down
+/// @dev bps_of(amount, bps) = floor(amount * bps / 10000), bps above 10000 scale the amount up
+/// @param amount Amount to take the share of
+/// @param bps Share in basis points
+/// @return Result<u64> The share - fails with ArithmeticOverflow if it doesn't fit in a u64
+pub(crate) fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    mul_div_floor(amount, bps as u64, BPS_DENOMINATOR)
+}
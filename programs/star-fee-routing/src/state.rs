@@ -5,12 +5,41 @@ use anchor_lang::prelude::*;
 pub struct GlobalState {
     /// The creator's quote token ATA to receive remaining fees
     pub creator_quote_ata: Pubkey,
+    /// Authority allowed to perform privileged configuration updates
+    pub authority: Pubkey,
+    /// Emergency stop, blocks fee claiming and distribution while set
+    pub paused: bool,
+    /// Quote mint every distribution crank must use
+    pub quote_mint: Pubkey,
+    /// Optional signer allowed to open a new distribution period before the interval has elapsed
+    pub crank_authority: Option<Pubkey>,
+    /// Honorary positions created and not yet transferred out, the deployment can only be closed at 0
+    pub open_positions: u32,
+    /// Set when migrated from the legacy layout, whose positions were never counted; such a deployment can't be closed
+    pub untracked_positions: bool,
+    /// Schema version of the account layout
+    pub version: u8,
     /// Bump seed for the global state PDA
     pub bump: u8,
 }
 
 impl GlobalState {
-    pub const LEN: usize = 8 + 32 + 1; // discriminator + pubkey + bump
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 32 + 33 + 4 + 1 + 1 + 1; // discriminator + creator_quote_ata + authority + paused + quote_mint + crank_authority + open_positions + untracked_positions + version + bump
+    /// Schema version written by this program
+    pub const VERSION: u8 = 1;
+}
+
+/// GlobalState layout of the first deployments, read by migrate_global_state
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LegacyGlobalState {
+    /// The creator's quote token ATA to receive remaining fees
+    pub creator_quote_ata: Pubkey,
+    /// Bump seed for the global state PDA
+    pub bump: u8,
+}
+
+impl LegacyGlobalState {
+    pub const LEN: usize = 8 + 32 + 1; // discriminator + creator_quote_ata + bump
 }
 
 /// Distribution progress tracking for the 24h crank
@@ -24,15 +53,86 @@ pub struct DistributionProgress {
     pub carry_over: u64,
     /// Current page index for pagination
     pub page_cursor: u32,
+    /// Number of pages in the current day, fixed on page 0
+    pub total_pages: u32,
     /// Whether the current day's distribution is complete
     pub day_complete: bool,
     /// Vault seed for this distribution
     pub vault_seed: u64,
+    /// Highest stream pubkey processed today, streams must be passed in ascending order
+    pub last_processed_stream: Pubkey,
+    /// Timestamp of the last successfully processed page (unix timestamp)
+    pub last_page_ts: i64,
+    /// Total quote fees ever claimed for this vault
+    pub lifetime_claimed: u64,
+    /// Total quote ever paid to investors
+    pub lifetime_investor_distributed: u64,
+    /// Total quote ever paid to the creator
+    pub lifetime_creator_distributed: u64,
+    /// Number of distribution days closed
+    pub completed_days: u32,
+    /// Locked amount of all the day's streams, fixed on page 0 (0 = each page uses its own streams)
+    pub day_total_locked: u64,
+    /// Y0 total of the day, fixed on page 0 together with day_total_locked
+    pub day_y0_total: u64,
+    /// Locked amount of the streams processed so far today, never above a day_total_locked snapshot
+    pub day_locked_processed: u64,
+    /// Quote fees claimed by the current day's pages
+    pub day_claimed: u64,
+    /// Investors paid by the current day's pages
+    pub day_investor_count: u32,
+    /// Schema version of the account layout
+    pub version: u8,
     /// Bump seed for the PDA
     pub bump: u8,
 }
 
 impl DistributionProgress {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 4 + 4 + 1 + 8 + 32 + 8 + 8 + 8 + 8 + 4 + 8 + 8 + 8 + 8 + 4 + 1 + 1; // discriminator + fields + version + bump
+    /// Schema version written by this program
+    pub const VERSION: u8 = 1;
+
+    /// Whether the current day's distribution has closed
+    pub fn is_day_complete(&self) -> bool {
+        self.day_complete
+    }
+
+    /// Whether page 0 has run but the day still has pages to resume from the cursor
+    pub fn is_day_in_progress(&self) -> bool {
+        self.page_cursor > 0 && !self.day_complete
+    }
+
+    /// Whether page 0 may open a new day at `now`, the same rule the crank applies without a crank authority
+    pub fn needs_new_day(&self, now: i64, interval: i64) -> bool {
+        !self.is_day_in_progress() && now >= self.last_distribution_ts.saturating_add(interval)
+    }
+
+    /// Pages of a `total`-page day still to be cranked from the cursor
+    pub fn pages_remaining(&self, total: u32) -> u32 {
+        total.saturating_sub(self.page_cursor)
+    }
+}
+
+/// DistributionProgress layout of the first deployments, read by migrate_distribution_progress
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LegacyDistributionProgress {
+    /// Last distribution timestamp (unix timestamp)
+    pub last_distribution_ts: i64,
+    /// Total quote fees distributed today
+    pub daily_distributed: u64,
+    /// Carried over amount from previous distributions (dust)
+    pub carry_over: u64,
+    /// Current page index for pagination
+    pub page_cursor: u32,
+    /// Whether the current day's distribution is complete
+    pub day_complete: bool,
+    /// Vault seed for this distribution
+    pub vault_seed: u64,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl LegacyDistributionProgress {
     pub const LEN: usize = 8 + 8 + 8 + 8 + 4 + 1 + 8 + 1; // discriminator + fields + bump
 }
 
@@ -49,12 +149,120 @@ pub struct PolicyConfig {
     pub y0_total: u64,
     /// Vault seed
     pub vault_seed: u64,
+    /// Seconds between distributions (86400 by default)
+    pub distribution_interval_seconds: i64,
+    /// Whether every investor pair must be proven against the investor allowlist
+    pub require_allowlist: bool,
+    /// How investor shares are rounded within a page
+    pub rounding_mode: RoundingMode,
+    /// Whether payouts to closed or frozen investor ATAs are skipped and carried over instead of failing the crank
+    pub skip_unpayable_investors: bool,
+    /// Minimum share of the fees the creator keeps in basis points, the investor pool is clamped to the rest
+    pub creator_floor_bps: u16,
+    /// Streams locking less than this are left out of the distribution, their share goes to the creator
+    pub min_locked_to_participate: u64,
+    /// Creator amounts below this are carried to the next day instead of transferred
+    pub creator_sweep_threshold: u64,
+    /// Whether page 0 may run without investor pairs, sending the day's fees to the creator
+    pub allow_empty_investor_set: bool,
+    /// Whether every page must pay exactly the investors registered for it in the investor registry
+    pub require_investor_registry: bool,
+    /// Start of the distribution schedule, periods begin at epoch_start_ts + n * interval (0 = first crank)
+    pub epoch_start_ts: i64,
+    /// Whether streams that fail to deserialize are skipped with zero locked instead of failing the crank
+    pub skip_invalid_streams: bool,
+    /// Whether the vault is past full vesting, every day goes to the creator without stream accounts
+    pub creator_only: bool,
+    /// Optional ceiling on what a single investor receives per day, the clipped excess is carried over
+    pub per_investor_daily_cap: Option<u64>,
+    /// Whether each closed day is summarized in an SPL Memo for explorers
+    pub emit_memo: bool,
+    /// Schema version of the account layout
+    pub version: u8,
     /// Bump seed for the PDA
     pub bump: u8,
 }
 
 impl PolicyConfig {
-    pub const LEN: usize = 8 + 2 + 9 + 8 + 8 + 8 + 1; // discriminator + fields + bump
+    pub const LEN: usize = 8 + 2 + 9 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 2 + 8 + 8 + 1 + 1 + 8 + 1 + 1 + 9 + 1 + 1 + 1; // discriminator + fields + version + bump
+    /// Schema version written by this program
+    pub const VERSION: u8 = 1;
+}
+
+/// Policy fields of a new vault, taken by initialize_policy_config and initialize_vault
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PolicyConfigParams {
+    /// Basis points allocated to investors (max 10000)
+    pub investor_fee_share_bps: u16,
+    /// Optional daily cap on investor payouts
+    pub daily_cap_lamports: Option<u64>,
+    /// Minimum payout threshold to prevent dust transactions
+    pub min_payout_lamports: u64,
+    /// Total investor allocation at TGE (Y0)
+    pub y0_total: u64,
+    /// Seconds between distributions, defaults to 24h when None
+    pub distribution_interval_seconds: Option<i64>,
+    /// Require a Merkle proof against the investor allowlist for every investor pair
+    pub require_allowlist: bool,
+    /// How investor shares are rounded within a page
+    pub rounding_mode: RoundingMode,
+    /// Skip and carry over payouts to closed or frozen investor ATAs
+    pub skip_unpayable_investors: bool,
+    /// Minimum share of the fees the creator keeps (max 10000)
+    pub creator_floor_bps: u16,
+    /// Minimum locked amount for a stream to take part in a distribution
+    pub min_locked_to_participate: u64,
+    /// Creator amounts below this are carried to the next day instead of transferred
+    pub creator_sweep_threshold: u64,
+    /// Let page 0 run without investor pairs, sweeping the day's fees to the creator
+    pub allow_empty_investor_set: bool,
+    /// Pay exactly the investors registered for each page, ignoring caller weights
+    pub require_investor_registry: bool,
+    /// Align distribution periods to epoch_start_ts + n * interval, 0 anchors them on the first crank
+    pub epoch_start_ts: i64,
+    /// Skip streams that fail to deserialize instead of failing the crank
+    pub skip_invalid_streams: bool,
+    /// Sweep every day to the creator without stream accounts, for fully vested vaults
+    pub creator_only: bool,
+    /// Optional ceiling on a single investor's daily payout, the excess is carried over
+    pub per_investor_daily_cap: Option<u64>,
+    /// Log a summary of each closed day through SPL Memo
+    pub emit_memo: bool,
+}
+
+/// Policy fields to change in update_policy_config, None leaves a field as it is
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PolicyConfigUpdate {
+    pub investor_fee_share_bps: Option<u16>,
+    /// Some(None) removes the daily cap
+    pub daily_cap_lamports: Option<Option<u64>>,
+    pub min_payout_lamports: Option<u64>,
+    pub y0_total: Option<u64>,
+    pub distribution_interval_seconds: Option<i64>,
+    pub require_allowlist: Option<bool>,
+    pub rounding_mode: Option<RoundingMode>,
+    pub skip_unpayable_investors: Option<bool>,
+    pub creator_floor_bps: Option<u16>,
+    pub min_locked_to_participate: Option<u64>,
+    pub creator_sweep_threshold: Option<u64>,
+    pub allow_empty_investor_set: Option<bool>,
+    pub require_investor_registry: Option<bool>,
+    pub epoch_start_ts: Option<i64>,
+    pub skip_invalid_streams: Option<bool>,
+    pub creator_only: Option<bool>,
+    /// Some(None) removes the per-investor cap
+    pub per_investor_daily_cap: Option<Option<u64>>,
+    pub emit_memo: Option<bool>,
+}
+
+/// Rounding policy for the per-investor shares of a page
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Floor every share, the rounding remainder stays in the treasury
+    #[default]
+    Floor,
+    /// Hand the floor remainder out one lamport at a time to the largest fractional remainders
+    LargestRemainder,
 }
 
 /// Investor data for fee distribution
@@ -64,10 +272,220 @@ pub struct InvestorData {
     pub stream_pubkey: Pubkey,
     /// Investor's quote token ATA
     pub investor_quote_ata: Pubkey,
+    /// Weight applied to the investor's locked amount in basis points (10000 = pro-rata)
+    pub weight_bps: u16,
 }
 
 impl InvestorData {
-    pub const LEN: usize = 32 + 32; // stream_pubkey + investor_quote_ata
+    pub const LEN: usize = 32 + 32 + 2; // stream_pubkey + investor_quote_ata + weight_bps
+    pub const DEFAULT_WEIGHT_BPS: u16 = 10000;
+}
+
+/// Outcome of a distribute_fees call, published via transaction return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DistributionResult {
+    /// Quote fees claimed on this call (0 on continuation pages)
+    pub claimed_quote: u64,
+    /// Total paid out to investors on this page
+    pub investor_total_distributed: u64,
+    /// Amount paid to the creator (0 until the last page)
+    pub creator_amount: u64,
+    /// Number of investors paid on this page
+    pub investor_count: u32,
+    /// Page index that was processed
+    pub page_index: u32,
+    /// Whether this call closed the day
+    pub day_complete: bool,
+    /// Number of investor pairs processed on this page before it ended
+    pub investors_processed: u32,
+    /// Whether the page stopped early on compute, the remaining investors go in one more page
+    pub more_pages_needed: bool,
+}
+
+/// Merkle root of the approved (stream, investor ATA) pairs of a vault
+#[account]
+pub struct InvestorAllowlist {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// Root of the Merkle tree whose leaves are sha256(stream_pubkey || investor_quote_ata)
+    pub merkle_root: [u8; 32],
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl InvestorAllowlist {
+    pub const LEN: usize = 8 + 8 + 32 + 1; // discriminator + vault_seed + merkle_root + bump
+}
+
+/// Locked amount of an investor in a lockup without a supported vesting program, attested by the authority
+#[account]
+pub struct RawLock {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// Wallet the locked tokens belong to, investor ATAs paired with the lock must be owned by it
+    pub recipient: Pubkey,
+    /// Amount currently locked
+    pub locked_amount: u64,
+    /// Amount originally locked, the lock's contribution to Y0
+    pub deposited_amount: u64,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl RawLock {
+    pub const LEN: usize = 8 + 8 + 32 + 8 + 8 + 1; // discriminator + vault_seed + recipient + amounts + bump
+}
+
+/// A Bonfida vesting account registered for a vault, with the wallet owning its destination token account
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BonfidaRegistration {
+    /// Bonfida vesting account
+    pub vesting: Pubkey,
+    /// Token account the vesting releases into when it was registered
+    pub destination_address: Pubkey,
+    /// Owner of the destination token account, investor ATAs paired with the vesting must be owned by it
+    pub recipient: Pubkey,
+    /// Amount originally vested, the vesting's contribution to Y0
+    pub deposited_amount: u64,
+}
+
+impl BonfidaRegistration {
+    pub const LEN: usize = 32 + 32 + 32 + 8; // vesting + destination_address + recipient + deposited_amount
+}
+
+/// Bonfida vesting accounts a vault may distribute to
+#[account]
+pub struct BonfidaRegistry {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// Registered vesting accounts, one entry per vesting
+    pub vestings: Vec<BonfidaRegistration>,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl BonfidaRegistry {
+    pub const MAX_VESTINGS: usize = 64;
+    pub const LEN: usize = 8 + 8 + 4 + Self::MAX_VESTINGS * BonfidaRegistration::LEN + 1; // discriminator + fields + bump
+
+    /// Registration of a vesting account, if it was registered
+    pub fn find(&self, vesting: &Pubkey) -> Option<&BonfidaRegistration> {
+        self.vestings.iter().find(|registration| registration.vesting == *vesting)
+    }
+}
+
+/// Quote paid to one investor wallet on the day being cranked
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WalletDayPayout {
+    /// Investor quote ATA
+    pub investor_ata: Pubkey,
+    /// Amount paid to the ATA on the day, across all of its pages
+    pub paid: u64,
+}
+
+impl WalletDayPayout {
+    pub const LEN: usize = 32 + 8; // investor_ata + paid
+}
+
+/// Payouts of the day being cranked per investor wallet, so the per-investor cap spans every page of the day
+#[account]
+pub struct InvestorCapLedger {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// Day the payouts belong to, the distribution progress's completed_days while it was cranked
+    pub day: u32,
+    /// Wallets paid on the day, one entry per ATA
+    pub wallets: Vec<WalletDayPayout>,
+}
+
+impl InvestorCapLedger {
+    pub const MAX_WALLETS: usize = 240;
+    pub const LEN: usize = 8 + 8 + 4 + 4 + Self::MAX_WALLETS * WalletDayPayout::LEN; // discriminator + fields
+
+    /// Amount paid to an investor ATA on the given day, nothing if the ledger holds another day
+    pub fn paid_on(&self, day: u32, investor_ata: &Pubkey) -> u64 {
+        if self.day != day {
+            return 0;
+        }
+
+        self.wallets.iter().find(|wallet| wallet.investor_ata == *investor_ata).map_or(0, |wallet| wallet.paid)
+    }
+}
+
+/// Summary of one closed distribution day of a vault, written by the day's closing page
+#[account]
+pub struct DistributionReceipt {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// Index of the day, the number of days the vault had closed before it
+    pub day: u32,
+    /// Quote fees claimed during the day
+    pub claimed: u64,
+    /// Quote paid to investors during the day
+    pub investor_total: u64,
+    /// Quote paid to the creator at day close
+    pub creator_amount: u64,
+    /// Investors paid during the day
+    pub investor_count: u32,
+    /// Locked amount the day's fees were split over
+    pub total_locked: u64,
+    /// Y0 total of the day
+    pub y0: u64,
+    /// Timestamp the day closed at (unix timestamp)
+    pub closed_ts: i64,
+}
+
+impl DistributionReceipt {
+    pub const LEN: usize = 8 + 8 + 4 + 8 + 8 + 8 + 4 + 8 + 8 + 8; // discriminator + vault_seed + day + fields + closed_ts
+}
+
+/// Investors registered for one distribution page of a vault
+#[account]
+pub struct InvestorRegistry {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// Distribution page these investors are paid on
+    pub page_index: u32,
+    /// Number of registered pages, the day's total_pages must match it
+    pub page_count: u32,
+    /// Registered investors in strictly ascending stream order
+    pub investors: Vec<InvestorData>,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl InvestorRegistry {
+    pub const MAX_INVESTORS: usize = crate::MAX_INVESTORS_PER_PAGE;
+    pub const LEN: usize = 8 + 8 + 4 + 4 + 4 + Self::MAX_INVESTORS * InvestorData::LEN + 1; // discriminator + fields + bump
+}
+
+/// One recipient of the creator remainder
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CreatorRecipient {
+    /// Recipient's quote token ATA
+    pub ata: Pubkey,
+    /// Share of the creator remainder in basis points
+    pub bps: u16,
+}
+
+impl CreatorRecipient {
+    pub const LEN: usize = 32 + 2; // ata + bps
+}
+
+/// Split of the creator remainder across several recipients
+#[account]
+pub struct CreatorSplit {
+    /// Vault seed
+    pub vault_seed: u64,
+    /// Recipients in payout order, bps sum to 10000
+    pub recipients: Vec<CreatorRecipient>,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl CreatorSplit {
+    pub const MAX_RECIPIENTS: usize = 5;
+    pub const LEN: usize = 8 + 8 + 4 + Self::MAX_RECIPIENTS * CreatorRecipient::LEN + 1; // discriminator + fields + bump
 }
 
 /// Seeds for PDAs
@@ -77,3 +495,10 @@ pub const INVESTOR_FEE_POSITION_OWNER_SEED: &[u8] = b"investor_fee_pos_owner";
 pub const DISTRIBUTION_PROGRESS_SEED: &[u8] = b"distribution_progress";
 pub const POLICY_CONFIG_SEED: &[u8] = b"policy_config";
 pub const QUOTE_TREASURY_SEED: &[u8] = b"quote_treasury";
+pub const CREATOR_SPLIT_SEED: &[u8] = b"creator_split";
+pub const INVESTOR_ALLOWLIST_SEED: &[u8] = b"investor_allowlist";
+pub const INVESTOR_REGISTRY_SEED: &[u8] = b"investor_registry";
+pub const RAW_LOCK_SEED: &[u8] = b"raw_lock";
+pub const DISTRIBUTION_RECEIPT_SEED: &[u8] = b"distribution_receipt";
+pub const BONFIDA_REGISTRY_SEED: &[u8] = b"bonfida_registry";
+pub const INVESTOR_CAP_LEDGER_SEED: &[u8] = b"investor_cap_ledger";
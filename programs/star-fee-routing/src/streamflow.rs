@@ -0,0 +1,165 @@
+use crate::FeeRoutingError;
+use anchor_lang::prelude::*;
+use streamflow_sdk::state::Contract as StreamflowContract;
+
+/// Size of a Streamflow metadata account, the Streamflow program rejects any other length
+pub const STREAMFLOW_METADATA_LEN: usize = 1104;
+
+/// Magic value the Streamflow program writes into every stream's metadata
+pub const STREAMFLOW_CONTRACT_MAGIC: u64 = 0;
+
+/// Highest Streamflow metadata version whose layout matches the pinned streamflow-sdk Contract
+pub const MAX_SUPPORTED_STREAMFLOW_VERSION: u8 = 0;
+
+/// Seconds a stream's created_at may run ahead of the crank's clock before it is rejected as implausible
+pub const MAX_STREAM_CLOCK_SKEW_SECONDS: i64 = 300;
+
+/// @notice Query locked token amount from a Streamflow contract for pro-rata distribution
+/// @dev Deserializes Streamflow contract data and calculates remaining locked tokens
+/// @dev Vested-but-unwithdrawn tokens are unlocked, so locked = deposited - max(vested(now), withdrawn)
+/// @param stream_account_info The Streamflow contract account containing stream data
+/// @param now Unix timestamp at which to evaluate the unlock schedule
+/// @return Result<u64> The amount of tokens currently locked in the stream
+pub fn get_locked_amount_from_streamflow(stream_account_info: &AccountInfo, now: i64) -> Result<u64> {
+    let stream_contract = load_streamflow_contract(stream_account_info)?;
+    validate_stream_timestamps(&stream_contract, now)?;
+    let locked_amount = compute_locked_amount(&stream_contract, now);
+
+    msg!("Streamflow contract analysis:");
+    msg!("  - Net deposited: {}", stream_contract.ix.net_amount_deposited);
+    msg!("  - Amount withdrawn: {}", stream_contract.amount_withdrawn);
+    msg!("  - Locked amount: {}", locked_amount);
+    msg!("  - Stream closed: {}", stream_contract.closed);
+
+    Ok(locked_amount)
+}
+
+/// @notice Compute the locked amount of an already-deserialized Streamflow contract
+/// @dev Pure variant of get_locked_amount_from_streamflow for off-chain crank bots and previews
+/// @dev Streamflow and partner fees are deliberately not netted out, see recipient_deposit
+/// @param stream_contract The deserialized Streamflow contract
+/// @param now Unix timestamp at which to evaluate the unlock schedule
+/// @return u64 The amount of tokens currently locked in the stream, 0 for closed streams
+pub fn compute_locked_amount(stream_contract: &StreamflowContract, now: i64) -> u64 {
+    if stream_contract.closed {
+        return 0;
+    }
+
+    // Withdrawn tokens can never exceed vested ones on-chain, max() keeps the result safe either way
+    let vested_amount = calculate_vested_amount(stream_contract, now);
+    let unlocked_amount = std::cmp::max(vested_amount, stream_contract.amount_withdrawn);
+
+    recipient_deposit(stream_contract).saturating_sub(unlocked_amount)
+}
+
+/// @notice Amount of a stream that flows to its recipient
+/// @dev Streamflow charges streamflow_fee_total and partner_fee_total on top of net_amount_deposited and
+/// @dev withdraws them separately (streamflow_fee_withdrawn, partner_fee_withdrawn), they never reach the
+/// @dev recipient and never count in amount_withdrawn; net_amount_deposited is already net of both, so
+/// @dev subtracting them again would under-count the investor's locked position
+/// @param stream_contract The deserialized Streamflow contract
+/// @return u64 The recipient's share of the deposit
+pub fn recipient_deposit(stream_contract: &StreamflowContract) -> u64 {
+    stream_contract.ix.net_amount_deposited
+}
+
+/// @notice Deserialize a Streamflow contract after checking it is owned by the Streamflow program
+/// @dev Without the owner check a caller could fabricate a stream with an arbitrary deposit
+/// @param stream_account_info The Streamflow contract account
+/// @return Result<StreamflowContract> - fails with InvalidStreamflowContract on owner or layout mismatch
+pub fn load_streamflow_contract(stream_account_info: &AccountInfo) -> Result<StreamflowContract> {
+    if stream_account_info.owner != &streamflow_sdk::id() {
+        msg!("Stream {} is not owned by the Streamflow program", stream_account_info.key());
+        return Err(FeeRoutingError::InvalidStreamflowContract.into());
+    }
+
+    // Streamflow contracts don't have discriminators, use borsh deserialize
+    use borsh::BorshDeserialize;
+    let stream_data = &stream_account_info.data.borrow()[..];
+    if stream_data.len() != STREAMFLOW_METADATA_LEN {
+        msg!(
+            "Stream {} has {} bytes of metadata, expected {}",
+            stream_account_info.key(),
+            stream_data.len(),
+            STREAMFLOW_METADATA_LEN
+        );
+        return Err(FeeRoutingError::InvalidStreamflowContract.into());
+    }
+    let mut data_slice = stream_data;
+    let stream_contract =
+        StreamflowContract::deserialize(&mut data_slice).map_err(|_| FeeRoutingError::InvalidStreamflowContract)?;
+    validate_streamflow_header(&stream_contract)?;
+
+    Ok(stream_contract)
+}
+
+/// @notice Check the magic and version of a Streamflow contract before trusting the rest of its data
+/// @dev Streamflow has no read instruction to CPI into, so the layout is pinned to the streamflow-sdk Contract;
+/// @dev metadata from an unknown layout must be rejected rather than read at shifted offsets
+/// @param stream_contract The deserialized Streamflow contract
+/// @return Result<()> - fails with InvalidStreamflowContract on an unexpected magic or version
+pub fn validate_streamflow_header(stream_contract: &StreamflowContract) -> Result<()> {
+    if stream_contract.magic != STREAMFLOW_CONTRACT_MAGIC {
+        msg!("Unexpected Streamflow magic {}", stream_contract.magic);
+        return Err(FeeRoutingError::InvalidStreamflowContract.into());
+    }
+
+    if stream_contract.version > MAX_SUPPORTED_STREAMFLOW_VERSION {
+        msg!(
+            "Unsupported Streamflow version {}, at most {} is supported",
+            stream_contract.version,
+            MAX_SUPPORTED_STREAMFLOW_VERSION
+        );
+        return Err(FeeRoutingError::InvalidStreamflowContract.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Check that a Streamflow contract's timestamps are consistent and not from the future
+/// @dev A stream can't be created after the current time (beyond a small clock skew) and can't end before it
+/// @dev starts; an end_time of 0 means no end is recorded and is accepted
+/// @param stream_contract The deserialized Streamflow contract
+/// @param now Unix timestamp of the crank
+/// @return Result<()> - fails with InvalidStreamflowContract on implausible timestamps
+pub fn validate_stream_timestamps(stream_contract: &StreamflowContract, now: i64) -> Result<()> {
+    let latest_created_at = now.saturating_add(MAX_STREAM_CLOCK_SKEW_SECONDS).max(0) as u64;
+    if stream_contract.created_at > latest_created_at {
+        msg!("Stream created_at {} is in the future (now {})", stream_contract.created_at, now);
+        return Err(FeeRoutingError::InvalidStreamflowContract.into());
+    }
+
+    if stream_contract.end_time > 0 && stream_contract.end_time < stream_contract.ix.start_time {
+        msg!("Stream ends at {} before it starts at {}", stream_contract.end_time, stream_contract.ix.start_time);
+        return Err(FeeRoutingError::InvalidStreamflowContract.into());
+    }
+
+    Ok(())
+}
+
+/// @notice Calculate how much of a Streamflow stream has vested at a given time
+/// @dev Nothing vests before max(start_time, cliff), cliff_amount unlocks at the cliff and then
+/// @dev amount_per_period unlocks every full period until end_time, when the whole deposit is vested
+/// @param stream_contract The deserialized Streamflow contract
+/// @param now Unix timestamp at which to evaluate the unlock schedule
+/// @return u64 The vested amount, capped at net_amount_deposited
+pub fn calculate_vested_amount(stream_contract: &StreamflowContract, now: i64) -> u64 {
+    let schedule = &stream_contract.ix;
+    let deposited = schedule.net_amount_deposited;
+    let now = now.max(0) as u64;
+    let cliff = std::cmp::max(schedule.start_time, schedule.cliff);
+
+    if now < cliff {
+        return 0;
+    }
+
+    if stream_contract.end_time > 0 && now >= stream_contract.end_time {
+        return deposited;
+    }
+
+    let periods_elapsed = (now - cliff).checked_div(schedule.period).unwrap_or(0);
+    let vested = (schedule.cliff_amount as u128)
+        .saturating_add((periods_elapsed as u128).saturating_mul(schedule.amount_per_period as u128));
+
+    std::cmp::min(vested, deposited as u128) as u64
+}
@@ -0,0 +1,212 @@
+use crate::{compute_locked_amount, load_streamflow_contract, recipient_deposit, validate_stream_timestamps};
+use crate::{BonfidaRegistry, FeeRoutingError, RawLock};
+use anchor_lang::prelude::*;
+use streamflow_sdk::state::Contract as StreamflowContract;
+
+/// Bonfida token-vesting program ID
+pub const BONFIDA_VESTING_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("CChTq6PthWU82YZkbveA3WDf7s97BWhBK4Vx9bmsT743");
+
+/// Size of a Bonfida vesting header: destination_address + mint_address + is_initialized
+pub const BONFIDA_HEADER_LEN: usize = 32 + 32 + 1;
+
+/// Size of one Bonfida vesting schedule: release_time + amount
+pub const BONFIDA_SCHEDULE_LEN: usize = 8 + 8;
+
+/// Vesting provider an investor's locked account is read from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockedAmountSource {
+    /// Streamflow stream metadata
+    Streamflow,
+    /// Bonfida token-vesting schedule
+    Bonfida,
+    /// RawLock account of this program, attested by the authority
+    Raw,
+}
+
+impl LockedAmountSource {
+    /// @notice Select the vesting provider of a locked account
+    /// @dev The account's owner decides, so one provider's layout can never be passed off as another's
+    /// @param locked_account_info The locked account paired with an investor ATA
+    /// @return Result<LockedAmountSource> - fails with InvalidStreamflowContract for an account of any other program
+    pub fn of(locked_account_info: &AccountInfo) -> Result<Self> {
+        if locked_account_info.owner == &streamflow_sdk::id() {
+            Ok(Self::Streamflow)
+        } else if locked_account_info.owner == &BONFIDA_VESTING_PROGRAM_ID {
+            Ok(Self::Bonfida)
+        } else if locked_account_info.owner == &crate::ID {
+            Ok(Self::Raw)
+        } else {
+            msg!("Locked account {} is not owned by a supported vesting provider", locked_account_info.key());
+            Err(FeeRoutingError::InvalidStreamflowContract.into())
+        }
+    }
+}
+
+/// A Bonfida vesting account: where tokens are released to and the release schedule
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BonfidaVesting {
+    /// Token account the vested tokens are released into
+    pub destination_address: Pubkey,
+    /// (release_time, amount) schedules, a released schedule's amount is zeroed once it is unlocked
+    pub schedules: Vec<(u64, u64)>,
+}
+
+impl BonfidaVesting {
+    /// Amount of the schedules not yet released at `now`
+    pub fn locked_amount(&self, now: i64) -> u64 {
+        let now = now.max(0) as u64;
+        self.schedules
+            .iter()
+            .filter(|(release_time, _)| *release_time > now)
+            .fold(0u64, |locked, (_, amount)| locked.saturating_add(*amount))
+    }
+
+    /// Amount of every schedule still held by the vesting account; released schedules are zeroed, so this
+    /// shrinks over time and can't serve as the vesting's Y0
+    pub fn held_amount(&self) -> u64 {
+        self.schedules.iter().fold(0u64, |deposited, (_, amount)| deposited.saturating_add(*amount))
+    }
+}
+
+/// @notice Parse the data of a Bonfida vesting account
+/// @param data The account data: a BONFIDA_HEADER_LEN header followed by BONFIDA_SCHEDULE_LEN schedules
+/// @return Result<BonfidaVesting> - fails with InvalidStreamflowContract for an uninitialized or malformed account
+pub fn parse_bonfida_vesting(data: &[u8]) -> Result<BonfidaVesting> {
+    let schedule_bytes = data.len().saturating_sub(BONFIDA_HEADER_LEN);
+    if data.len() <= BONFIDA_HEADER_LEN || !schedule_bytes.is_multiple_of(BONFIDA_SCHEDULE_LEN) || data[64] != 1 {
+        msg!("Bonfida vesting account has {} bytes or is not initialized", data.len());
+        return Err(FeeRoutingError::InvalidStreamflowContract.into());
+    }
+
+    let destination_address = Pubkey::try_from(&data[..32]).map_err(|_| FeeRoutingError::InvalidStreamflowContract)?;
+    let schedules = data[BONFIDA_HEADER_LEN..]
+        .chunks_exact(BONFIDA_SCHEDULE_LEN)
+        .map(|schedule| {
+            let release_time = u64::from_le_bytes(schedule[..8].try_into().unwrap());
+            let amount = u64::from_le_bytes(schedule[8..].try_into().unwrap());
+            (release_time, amount)
+        })
+        .collect();
+
+    Ok(BonfidaVesting { destination_address, schedules })
+}
+
+/// A locked account deserialized with its provider's layout
+enum LockedAccount {
+    Streamflow(Box<StreamflowContract>),
+    Bonfida(BonfidaVesting),
+    Raw(RawLock),
+}
+
+/// @notice Deserialize a locked account with the layout of its provider
+/// @dev A RawLock must belong to the vault being distributed, so one vault's attestations can't be replayed in another
+/// @param locked_account_info The locked account paired with an investor ATA
+/// @param vault_seed The vault being distributed
+/// @return Result<LockedAccount> - fails with InvalidStreamflowContract on an unreadable account and
+/// @return VaultSeedMismatch for another vault's RawLock
+fn load_locked_account(locked_account_info: &AccountInfo, vault_seed: u64) -> Result<LockedAccount> {
+    match LockedAmountSource::of(locked_account_info)? {
+        LockedAmountSource::Streamflow => {
+            Ok(LockedAccount::Streamflow(Box::new(load_streamflow_contract(locked_account_info)?)))
+        }
+        LockedAmountSource::Bonfida => {
+            Ok(LockedAccount::Bonfida(parse_bonfida_vesting(&locked_account_info.try_borrow_data()?)?))
+        }
+        LockedAmountSource::Raw => {
+            let raw_lock = RawLock::try_deserialize(&mut &locked_account_info.try_borrow_data()?[..])
+                .map_err(|_| FeeRoutingError::InvalidStreamflowContract)?;
+            require_eq!(raw_lock.vault_seed, vault_seed, FeeRoutingError::VaultSeedMismatch);
+            Ok(LockedAccount::Raw(raw_lock))
+        }
+    }
+}
+
+/// @notice Whether a locked account can be deserialized by its provider
+/// @dev Only load failures count, a readable Streamflow stream with implausible timestamps is still loadable
+/// @param locked_account_info The locked account paired with an investor ATA
+/// @param vault_seed The vault being distributed
+/// @return bool - true when the account loads
+pub fn is_loadable_locked_account(locked_account_info: &AccountInfo, vault_seed: u64) -> bool {
+    load_locked_account(locked_account_info, vault_seed).is_ok()
+}
+
+/// An investor's locked position, read from any supported vesting provider
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockedPosition {
+    /// Provider the position was read from
+    pub source: LockedAmountSource,
+    /// Wallet the locked tokens belong to, for Bonfida the registered owner of the destination account
+    pub recipient: Pubkey,
+    /// Token account the tokens are released into, accepted as the investor ATA as is
+    pub recipient_tokens: Option<Pubkey>,
+    /// Amount still locked at the crank's time
+    pub locked_amount: u64,
+    /// Amount originally locked, the position's contribution to Y0
+    pub deposited_amount: u64,
+}
+
+/// @notice Read an investor's locked position from its locked account
+/// @dev Streamflow streams are evaluated at `now` as in get_locked_amount_from_streamflow, Bonfida schedules count
+/// @dev as locked until their release time, and a RawLock holds the locked amount last set by the authority
+/// @dev A Bonfida vesting is paid to the wallet registered for it, its destination must not have changed since,
+/// @dev and counts towards Y0 with the deposit registered for it
+/// @param locked_account_info The locked account paired with an investor ATA
+/// @param vault_seed The vault being distributed
+/// @param now Unix timestamp at which to evaluate the unlock schedule
+/// @param bonfida_registry The vault's Bonfida registry, if passed
+/// @return Result<LockedPosition> - fails with InvalidStreamflowContract on an unreadable account or implausible
+/// @return stream timestamps, VaultSeedMismatch for another vault's RawLock and UnregisteredBonfidaVesting
+pub fn load_locked_position(
+    locked_account_info: &AccountInfo,
+    vault_seed: u64,
+    now: i64,
+    bonfida_registry: Option<&BonfidaRegistry>,
+) -> Result<LockedPosition> {
+    let position = match load_locked_account(locked_account_info, vault_seed)? {
+        LockedAccount::Streamflow(stream_contract) => {
+            validate_stream_timestamps(&stream_contract, now)?;
+            LockedPosition {
+                source: LockedAmountSource::Streamflow,
+                recipient: stream_contract.recipient,
+                recipient_tokens: Some(stream_contract.recipient_tokens),
+                locked_amount: compute_locked_amount(&stream_contract, now),
+                deposited_amount: recipient_deposit(&stream_contract),
+            }
+        }
+        LockedAccount::Bonfida(vesting) => {
+            let registration = bonfida_registry
+                .and_then(|registry| registry.find(&locked_account_info.key()))
+                .filter(|registration| registration.destination_address == vesting.destination_address)
+                .ok_or_else(|| {
+                    msg!("Bonfida vesting {} is not registered with its destination", locked_account_info.key());
+                    FeeRoutingError::UnregisteredBonfidaVesting
+                })?;
+            // The destination holds the vested base mint, never a quote ATA, so only its owner is paired
+            LockedPosition {
+                source: LockedAmountSource::Bonfida,
+                recipient: registration.recipient,
+                recipient_tokens: None,
+                locked_amount: vesting.locked_amount(now),
+                deposited_amount: registration.deposited_amount,
+            }
+        }
+        LockedAccount::Raw(raw_lock) => LockedPosition {
+            source: LockedAmountSource::Raw,
+            recipient: raw_lock.recipient,
+            recipient_tokens: None,
+            locked_amount: raw_lock.locked_amount,
+            deposited_amount: raw_lock.deposited_amount,
+        },
+    };
+
+    msg!(
+        "{:?} account {}: {} locked of {} deposited",
+        position.source,
+        locked_account_info.key(),
+        position.locked_amount,
+        position.deposited_amount
+    );
+
+    Ok(position)
+}